@@ -0,0 +1,837 @@
+//! Decide whether data written with one version of a type can be read with another.
+//!
+//! Whether a change to a type breaks anything depends on how it is serialized. Renaming
+//! a field makes no difference to bincode, which does not write field names, but JSON
+//! can no longer read the field. [`check`] compares two shapes for a [`Format`] and
+//! says how compatible they are, with the reasons why:
+//!
+//! ```ignore
+//! let report = type_hash::compat::check(&old_shape, &Message::shape(), Format::Json);
+//! for reason in &report.reasons {
+//!     println!("{}", reason);
+//! }
+//! println!("{:?}, so a {:?} version bump", report.compatibility, report.semver_bump());
+//! ```
+//!
+//! The rules assume that types use serde's derived `Serialize` and `Deserialize`
+//! without attributes that change how they are written, such as `rename`, `default`,
+//! `flatten` or `deny_unknown_fields`.
+use crate::diff::{diff, DisplayHasher, FieldsKind, Path, Segment};
+use crate::shape::{Fields, Shape, Variant};
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// A serialization format, with the rules for which changes it can read across
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// bincode, with its default configuration
+    Bincode,
+    /// serde_json
+    Json,
+    /// MessagePack as written by `rmp_serde::to_vec_named`, which writes structs as maps
+    MessagePack,
+    /// postcard
+    Postcard,
+}
+
+impl Format {
+    /// What the format writes, which decides the changes that it can read across
+    pub fn profile(self) -> Profile {
+        match self {
+            Format::Bincode => Profile {
+                field_names: false,
+                variant_names: false,
+                ignores_unknown_fields: false,
+                missing_option_is_none: false,
+                some_is_untagged: false,
+                widening_integers_from: None,
+                widening_floats: false,
+            },
+            Format::Json | Format::MessagePack => Profile {
+                field_names: true,
+                variant_names: true,
+                ignores_unknown_fields: true,
+                missing_option_is_none: true,
+                some_is_untagged: true,
+                widening_integers_from: Some(8),
+                widening_floats: true,
+            },
+            Format::Postcard => Profile {
+                field_names: false,
+                variant_names: false,
+                ignores_unknown_fields: false,
+                missing_option_is_none: false,
+                some_is_untagged: false,
+                // Only integers wider than a byte are written as varints
+                widening_integers_from: Some(16),
+                widening_floats: false,
+            },
+        }
+    }
+}
+
+/// The properties of a serialization format that decide which changes it can read
+/// across
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Profile {
+    /// Struct fields are written with their names. Otherwise they are identified by
+    /// their position.
+    pub field_names: bool,
+    /// Enum variants are written with their names. Otherwise they are identified by
+    /// their index.
+    pub variant_names: bool,
+    /// Fields that the reader does not know about are skipped
+    pub ignores_unknown_fields: bool,
+    /// A named `Option` field that is missing is read as `None`
+    pub missing_option_is_none: bool,
+    /// `Some(x)` is written in the same way as `x`, so a `T` can be read as an
+    /// `Option<T>`
+    pub some_is_untagged: bool,
+    /// Integers of at least this many bits can be read as a wider integer of the same
+    /// signedness
+    pub widening_integers_from: Option<u32>,
+    /// An `f32` can be read as an `f64`
+    pub widening_floats: bool,
+}
+
+/// How compatible a new version of a type is with an old one.
+///
+/// Backward compatible means that the new version can read data written with the old
+/// one, so readers can be upgraded first. Forward compatible means that the old version
+/// can read data written with the new one, so writers can be upgraded first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Compatibility {
+    /// The types have the same shape
+    Identical,
+    /// The shapes are different, but each version can read data written with the
+    /// other
+    Compatible,
+    /// The new version can read data written with the old one, but not the reverse
+    Backward,
+    /// The old version can read data written with the new one, but not the reverse
+    Forward,
+    /// Neither version can read all data written with the other
+    Breaking,
+}
+
+impl Compatibility {
+    fn new(backward: bool, forward: bool) -> Compatibility {
+        match (backward, forward) {
+            (true, true) => Compatibility::Compatible,
+            (true, false) => Compatibility::Backward,
+            (false, true) => Compatibility::Forward,
+            (false, false) => Compatibility::Breaking,
+        }
+    }
+
+    /// The new version can read data written with the old one
+    pub fn is_backward(self) -> bool {
+        matches!(
+            self,
+            Compatibility::Identical | Compatibility::Compatible | Compatibility::Backward
+        )
+    }
+
+    /// The old version can read data written with the new one
+    pub fn is_forward(self) -> bool {
+        matches!(
+            self,
+            Compatibility::Identical | Compatibility::Compatible | Compatibility::Forward
+        )
+    }
+
+    /// The compatibility of several changes together
+    fn and(self, other: Compatibility) -> Compatibility {
+        Compatibility::new(
+            self.is_backward() && other.is_backward(),
+            self.is_forward() && other.is_forward(),
+        )
+    }
+}
+
+/// The semver version bump for a change to a type that is part of a crate's API
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SemverBump {
+    None,
+    Patch,
+    Minor,
+    Major,
+}
+
+/// A change and its compatibility
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reason {
+    pub path: Path,
+    pub compatibility: Compatibility,
+    pub description: String,
+}
+
+/// The result of comparing two versions of a type for a format
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Report {
+    pub compatibility: Compatibility,
+    pub reasons: Vec<Reason>,
+}
+
+impl Report {
+    /// The version bump for the change.
+    ///
+    /// Changes that can be read in both directions are a patch. Backward compatible
+    /// changes are a minor version, because data written by the new version may not
+    /// be readable by the old one. Anything else means that data that could be read
+    /// before can't be read now, which is a major version.
+    pub fn semver_bump(&self) -> SemverBump {
+        match self.compatibility {
+            Compatibility::Identical => SemverBump::None,
+            Compatibility::Compatible => SemverBump::Patch,
+            Compatibility::Backward => SemverBump::Minor,
+            Compatibility::Forward | Compatibility::Breaking => SemverBump::Major,
+        }
+    }
+}
+
+/// Compare two versions of a type, for a format
+pub fn check(old: &Shape, new: &Shape, format: Format) -> Report {
+    check_with_profile(old, new, &format.profile())
+}
+
+/// Compare two versions of a type, for a format that is described by its profile
+pub fn check_with_profile(old: &Shape, new: &Shape, profile: &Profile) -> Report {
+    if diff(old, new).is_empty() {
+        return Report {
+            compatibility: Compatibility::Identical,
+            reasons: Vec::new(),
+        };
+    }
+    let mut checker = Checker {
+        profile,
+        path: Path::default(),
+        reasons: Vec::new(),
+    };
+    if let Some(name) = old.type_name() {
+        checker.path.0.push(Segment::Type(name.to_owned()));
+    }
+    checker.shapes(old, new);
+    let compatibility = checker
+        .reasons
+        .iter()
+        .fold(Compatibility::Compatible, |compatibility, reason| {
+            compatibility.and(reason.compatibility)
+        });
+    Report {
+        compatibility,
+        reasons: checker.reasons,
+    }
+}
+
+struct Checker<'a> {
+    profile: &'a Profile,
+    path: Path,
+    reasons: Vec<Reason>,
+}
+
+impl Checker<'_> {
+    fn reason(&mut self, compatibility: Compatibility, description: String) {
+        self.reasons.push(Reason {
+            path: self.path.clone(),
+            compatibility,
+            description,
+        });
+    }
+
+    fn within<F: FnOnce(&mut Self)>(&mut self, segment: Segment, f: F) {
+        self.path.0.push(segment);
+        f(self);
+        self.path.0.pop();
+    }
+
+    fn shapes(&mut self, old: &Shape, new: &Shape) {
+        if diff(old, new).is_empty() {
+            return;
+        }
+        match (old, new) {
+            (
+                Shape::Struct {
+                    name: old_name,
+                    fields: old_fields,
+                },
+                Shape::Struct {
+                    name: new_name,
+                    fields: new_fields,
+                },
+            ) => {
+                self.renamed_type(old_name, new_name);
+                self.fields(old_fields, new_fields);
+            }
+            (
+                Shape::Enum {
+                    name: old_name,
+                    variants: old_variants,
+                },
+                Shape::Enum {
+                    name: new_name,
+                    variants: new_variants,
+                },
+            ) => {
+                self.renamed_type(old_name, new_name);
+                self.variants(old_variants, new_variants);
+            }
+            (
+                Shape::Sequence {
+                    name: old_name,
+                    element: old_element,
+                    hasher: old_hasher,
+                },
+                Shape::Sequence {
+                    name: new_name,
+                    element: new_element,
+                    hasher: new_hasher,
+                },
+            ) => {
+                self.renamed_collection(old, new, old_name, new_name, "sequences");
+                self.changed_hasher(old_hasher, new_hasher);
+                self.within(Segment::Element, |checker| {
+                    checker.shapes(old_element, new_element)
+                });
+            }
+            (
+                Shape::Map {
+                    name: old_name,
+                    key: old_key,
+                    value: old_value,
+                    hasher: old_hasher,
+                },
+                Shape::Map {
+                    name: new_name,
+                    key: new_key,
+                    value: new_value,
+                    hasher: new_hasher,
+                },
+            ) => {
+                self.renamed_collection(old, new, old_name, new_name, "maps");
+                self.changed_hasher(old_hasher, new_hasher);
+                self.within(Segment::Key, |checker| checker.shapes(old_key, new_key));
+                self.within(Segment::Value, |checker| {
+                    checker.shapes(old_value, new_value)
+                });
+            }
+            (
+                Shape::Array {
+                    len: old_len,
+                    element: old_element,
+                },
+                Shape::Array {
+                    len: new_len,
+                    element: new_element,
+                },
+            ) if old_len == new_len => self.within(Segment::Element, |checker| {
+                checker.shapes(old_element, new_element)
+            }),
+            (Shape::Tuple(old_elements), Shape::Tuple(new_elements))
+                if old_elements.len() == new_elements.len() =>
+            {
+                for (index, (old, new)) in old_elements.iter().zip(new_elements).enumerate() {
+                    self.within(Segment::Index(index), |checker| checker.shapes(old, new));
+                }
+            }
+            (Shape::Slice(old), Shape::Slice(new)) => self.shapes(old, new),
+            // References are written as the value that they refer to
+            (
+                Shape::Reference {
+                    mutable: old_mutable,
+                    target: old,
+                },
+                Shape::Reference {
+                    mutable: new_mutable,
+                    target: new,
+                },
+            ) => {
+                self.changed_mutability(*old_mutable, *new_mutable, ["&", "&mut "]);
+                self.shapes(old, new)
+            }
+            (
+                Shape::Pointer {
+                    mutable: old_mutable,
+                    target: old,
+                },
+                Shape::Pointer {
+                    mutable: new_mutable,
+                    target: new,
+                },
+            ) => {
+                self.changed_mutability(*old_mutable, *new_mutable, ["*const ", "*mut "]);
+                self.shapes(old, new)
+            }
+            (
+                Shape::Opaque {
+                    name: old_name,
+                    args: old_args,
+                },
+                Shape::Opaque {
+                    name: new_name,
+                    args: new_args,
+                },
+            ) if old_name == new_name && old_args.len() == new_args.len() => {
+                for (index, (old, new)) in old_args.iter().zip(new_args).enumerate() {
+                    self.within(Segment::Arg(index), |checker| checker.shapes(old, new));
+                }
+            }
+            (Shape::Primitive(old_name), Shape::Primitive(new_name))
+                if self.widens(old_name, new_name) =>
+            {
+                self.reason(
+                    Compatibility::Backward,
+                    format!(
+                        "{} -> {}: a {} can be read as a {}, but not the reverse",
+                        old, new, old, new
+                    ),
+                );
+            }
+            (_, Shape::Opaque { name, args })
+                if name == "std::option::Option" && args.len() == 1 && args[0] == *old =>
+            {
+                if self.profile.some_is_untagged {
+                    self.reason(
+                        Compatibility::Backward,
+                        format!(
+                            "{} -> {}: a value can be read as `Some`, but `None` can't be read as a value",
+                            old, new
+                        ),
+                    );
+                } else {
+                    self.reason(
+                        Compatibility::Breaking,
+                        format!("{} -> {}: `Option` is written with a tag", old, new),
+                    );
+                }
+            }
+            (
+                Shape::ConstArgs {
+                    args: old_args,
+                    shape: old_shape,
+                },
+                Shape::ConstArgs {
+                    args: new_args,
+                    shape: new_shape,
+                },
+            ) => {
+                if old_args != new_args {
+                    self.reason(
+                        Compatibility::Compatible,
+                        format!("{} -> {}: const arguments are not written", old, new),
+                    );
+                }
+                self.shapes(old_shape, new_shape);
+            }
+            // serde can't derive `Deserialize` for unions, so there are no rules to follow
+            (Shape::Union { .. }, Shape::Union { .. }) => self.reason(
+                Compatibility::Breaking,
+                "the fields of a union changed".to_owned(),
+            ),
+            _ => self.reason(Compatibility::Breaking, format!("{} -> {}", old, new)),
+        }
+    }
+
+    fn renamed_type(&mut self, old_name: &str, new_name: &str) {
+        if old_name != new_name {
+            self.reason(
+                Compatibility::Compatible,
+                format!(
+                    "renamed {} -> {}: the names of types are not written",
+                    old_name, new_name
+                ),
+            );
+        }
+    }
+
+    /// `kinds` are how a shared and a mutable reference or pointer are written
+    fn changed_mutability(&mut self, old: bool, new: bool, kinds: [&str; 2]) {
+        if old != new {
+            self.reason(
+                Compatibility::Compatible,
+                format!(
+                    "{}T -> {}T: mutability doesn't change how the value is written",
+                    kinds[old as usize], kinds[new as usize]
+                ),
+            );
+        }
+    }
+
+    fn changed_hasher(&mut self, old: &Option<Box<Shape>>, new: &Option<Box<Shape>>) {
+        if old != new {
+            self.reason(
+                Compatibility::Compatible,
+                format!(
+                    "hasher {} -> {}: the hasher doesn't change how the collection is written",
+                    DisplayHasher(old.as_deref()),
+                    DisplayHasher(new.as_deref())
+                ),
+            );
+        }
+    }
+
+    fn renamed_collection(
+        &mut self,
+        old: &Shape,
+        new: &Shape,
+        old_name: &str,
+        new_name: &str,
+        kind: &str,
+    ) {
+        if old_name != new_name {
+            self.reason(
+                Compatibility::Compatible,
+                format!("{} -> {}: both are written as {}", old, new, kind),
+            );
+        }
+    }
+
+    /// Whether a value of the primitive type `old` can be read as a `new`
+    fn widens(&self, old: &str, new: &str) -> bool {
+        // `usize` and `isize` are written as 64 bit integers
+        let int = |name: &str| match name {
+            "usize" => Some(('u', 64)),
+            "isize" => Some(('i', 64)),
+            _ => {
+                let (signedness, bits) = name.split_at(1);
+                match (signedness, bits.parse::<u32>()) {
+                    ("u", Ok(bits)) => Some(('u', bits)),
+                    ("i", Ok(bits)) => Some(('i', bits)),
+                    _ => None,
+                }
+            }
+        };
+        match (int(old), int(new)) {
+            (Some((old_sign, old_bits)), Some((new_sign, new_bits))) => {
+                let from = self.profile.widening_integers_from;
+                old_sign == new_sign
+                    && old_bits < new_bits
+                    && from.is_some_and(|from| old_bits >= from)
+            }
+            _ => self.profile.widening_floats && old == "f32" && new == "f64",
+        }
+    }
+
+    fn fields(&mut self, old: &Fields, new: &Fields) {
+        let (old_kind, new_kind) = (FieldsKind::of(old), FieldsKind::of(new));
+        match (old, new) {
+            (Fields::Unit, Fields::Unit) => {}
+            (Fields::Named(old), Fields::Named(new)) if self.profile.field_names => {
+                let old: Vec<_> = old.iter().map(|f| (f.name.as_str(), &f.shape)).collect();
+                let new: Vec<_> = new.iter().map(|f| (f.name.as_str(), &f.shape)).collect();
+                self.named_fields(&old, &new);
+            }
+            (Fields::Unnamed(_), Fields::Unnamed(_)) => {
+                self.positional_fields(&positional(old), &positional(new))
+            }
+            (Fields::Named(_), _) | (Fields::Unnamed(_), _)
+                if !self.profile.field_names && new_kind != FieldsKind::Unit =>
+            {
+                if old_kind != new_kind {
+                    self.reason(
+                        Compatibility::Compatible,
+                        format!(
+                            "{} -> {}: fields are written by position",
+                            old_kind, new_kind
+                        ),
+                    );
+                }
+                self.positional_fields(&positional(old), &positional(new));
+            }
+            _ => self.reason(
+                Compatibility::Breaking,
+                format!("{} -> {}", old_kind, new_kind),
+            ),
+        }
+    }
+
+    /// Fields that are identified by name
+    fn named_fields(&mut self, old: &[(&str, &Shape)], new: &[(&str, &Shape)]) {
+        let profile = self.profile;
+        let old_names: Vec<_> = old.iter().map(|(name, _)| *name).collect();
+        let new_names: Vec<_> = new.iter().map(|(name, _)| *name).collect();
+        for (name, old_shape) in old {
+            self.within(
+                Segment::Field(name.to_string()),
+                |checker| match find_field(new, name) {
+                    Some(new_shape) => {
+                        if moved(&old_names, &new_names, name) {
+                            checker.reason(
+                                Compatibility::Compatible,
+                                "moved: fields are written with their names".to_owned(),
+                            );
+                        }
+                        checker.shapes(old_shape, new_shape)
+                    }
+                    None => {
+                        let backward = profile.ignores_unknown_fields;
+                        let forward = profile.missing_option_is_none && is_option(old_shape);
+                        checker.reason(
+                            Compatibility::new(backward, forward),
+                            format!(
+                                "removed: {}, {}",
+                                if backward {
+                                    "the new version skips the field"
+                                } else {
+                                    "the new version can't skip the field"
+                                },
+                                if forward {
+                                    "and the old version reads it as `None`"
+                                } else {
+                                    "and the old version needs it"
+                                }
+                            ),
+                        );
+                    }
+                },
+            );
+        }
+        for (name, new_shape) in new {
+            if find_field(old, name).is_none() {
+                self.within(Segment::Field(name.to_string()), |checker| {
+                    let backward = profile.missing_option_is_none && is_option(new_shape);
+                    let forward = profile.ignores_unknown_fields;
+                    checker.reason(
+                        Compatibility::new(backward, forward),
+                        format!(
+                            "added: {}, {}",
+                            if backward {
+                                "the new version reads it as `None` when it is missing"
+                            } else {
+                                "the new version needs it"
+                            },
+                            if forward {
+                                "and the old version skips it"
+                            } else {
+                                "and the old version can't skip it"
+                            }
+                        ),
+                    );
+                });
+            }
+        }
+    }
+
+    /// Fields that are identified by position
+    fn positional_fields(&mut self, old: &[(Segment, &Shape)], new: &[(Segment, &Shape)]) {
+        for index in 0..old.len().max(new.len()) {
+            match (old.get(index), new.get(index)) {
+                (Some((old_segment, old_shape)), Some((new_segment, new_shape))) => {
+                    self.within(new_segment.clone(), |checker| {
+                        if let (Segment::Field(old_name), Segment::Field(new_name)) =
+                            (old_segment, new_segment)
+                        {
+                            checker.renamed_by_position(
+                                old_name,
+                                new_name,
+                                old.iter().map(|(s, _)| s),
+                                new.iter().map(|(s, _)| s),
+                                "field",
+                            );
+                        }
+                        checker.shapes(old_shape, new_shape);
+                    });
+                }
+                (Some((segment, _)), None) => self.within(segment.clone(), |checker| {
+                    checker.reason(
+                        Compatibility::Breaking,
+                        "removed: fields are written by position, so every field is needed"
+                            .to_owned(),
+                    )
+                }),
+                (None, Some((segment, _))) => self.within(segment.clone(), |checker| {
+                    checker.reason(
+                        Compatibility::Breaking,
+                        "added: fields are written by position, so every field is needed"
+                            .to_owned(),
+                    )
+                }),
+                (None, None) => unreachable!(),
+            }
+        }
+    }
+
+    /// A field or variant that is identified by position has a different name. This is
+    /// only a rename if neither name is used at another position.
+    fn renamed_by_position<'s>(
+        &mut self,
+        old_name: &str,
+        new_name: &str,
+        old: impl Iterator<Item = &'s Segment>,
+        new: impl Iterator<Item = &'s Segment>,
+        kind: &str,
+    ) {
+        if old_name == new_name {
+            return;
+        }
+        let name_of = |segment: &Segment| match segment {
+            Segment::Field(name) | Segment::Variant(name) => Some(name.clone()),
+            _ => None,
+        };
+        let old_names: Vec<_> = old.filter_map(name_of).collect();
+        let new_names: Vec<_> = new.filter_map(name_of).collect();
+        if new_names.iter().any(|name| name == old_name)
+            || old_names.iter().any(|name| name == new_name)
+        {
+            self.reason(
+                Compatibility::Breaking,
+                format!(
+                    "{} {} is now at the position of {}: {}s are identified by position",
+                    kind, new_name, old_name, kind
+                ),
+            );
+        } else {
+            self.reason(
+                Compatibility::Compatible,
+                format!(
+                    "renamed {} -> {}: the names of {}s are not written",
+                    old_name, new_name, kind
+                ),
+            );
+        }
+    }
+
+    fn variants(&mut self, old: &[Variant], new: &[Variant]) {
+        let profile = self.profile;
+        if profile.variant_names {
+            let old_names: Vec<_> = old.iter().map(|v| v.name.as_str()).collect();
+            let new_names: Vec<_> = new.iter().map(|v| v.name.as_str()).collect();
+            for old_variant in old {
+                self.within(
+                    Segment::Variant(old_variant.name.clone()),
+                    |checker| match new.iter().find(|v| v.name == old_variant.name) {
+                        Some(new_variant) => {
+                            if moved(&old_names, &new_names, &old_variant.name) {
+                                checker.reason(
+                                    Compatibility::Compatible,
+                                    "moved: variants are written with their names".to_owned(),
+                                );
+                            }
+                            checker.variant(old_variant, new_variant)
+                        }
+                        None => checker.reason(
+                            Compatibility::Forward,
+                            "removed: the new version can't read it".to_owned(),
+                        ),
+                    },
+                );
+            }
+            for new_variant in new {
+                if !old.iter().any(|v| v.name == new_variant.name) {
+                    self.within(Segment::Variant(new_variant.name.clone()), |checker| {
+                        checker.reason(
+                            Compatibility::Backward,
+                            "added: the old version can't read it".to_owned(),
+                        )
+                    });
+                }
+            }
+        } else {
+            let segments = |variants: &[Variant]| -> Vec<Segment> {
+                variants
+                    .iter()
+                    .map(|v| Segment::Variant(v.name.clone()))
+                    .collect()
+            };
+            let (old_segments, new_segments) = (segments(old), segments(new));
+            for index in 0..old.len().max(new.len()) {
+                match (old.get(index), new.get(index)) {
+                    (Some(old_variant), Some(new_variant)) => {
+                        self.within(new_segments[index].clone(), |checker| {
+                            checker.renamed_by_position(
+                                &old_variant.name,
+                                &new_variant.name,
+                                old_segments.iter(),
+                                new_segments.iter(),
+                                "variant",
+                            );
+                            checker.variant(old_variant, new_variant);
+                        });
+                    }
+                    (Some(_), None) => self.within(old_segments[index].clone(), |checker| {
+                        checker.reason(
+                            Compatibility::Forward,
+                            "removed: the new version can't read it".to_owned(),
+                        )
+                    }),
+                    (None, Some(_)) => self.within(new_segments[index].clone(), |checker| {
+                        checker.reason(
+                            Compatibility::Backward,
+                            "added: the old version can't read it".to_owned(),
+                        )
+                    }),
+                    (None, None) => unreachable!(),
+                }
+            }
+        }
+    }
+
+    fn variant(&mut self, old: &Variant, new: &Variant) {
+        if old.discriminant != new.discriminant {
+            self.reason(
+                Compatibility::Compatible,
+                "discriminant changed: discriminants are not written".to_owned(),
+            );
+        }
+        self.fields(&old.fields, &new.fields);
+    }
+}
+
+/// The fields, with the path segment for each, in order
+fn positional(fields: &Fields) -> Vec<(Segment, &Shape)> {
+    match fields {
+        Fields::Unit => Vec::new(),
+        Fields::Named(fields) => fields
+            .iter()
+            .map(|f| (Segment::Field(f.name.clone()), &f.shape))
+            .collect(),
+        Fields::Unnamed(fields) => fields
+            .iter()
+            .enumerate()
+            .map(|(index, shape)| (Segment::Index(index), shape))
+            .collect(),
+    }
+}
+
+/// Whether the item with this name is in a different order relative to the other items
+/// that are in both lists
+fn moved(old: &[&str], new: &[&str], name: &str) -> bool {
+    fn position_in_common(names: &[&str], others: &[&str], name: &str) -> Option<usize> {
+        names
+            .iter()
+            .filter(|n| others.contains(n))
+            .position(|n| *n == name)
+    }
+    position_in_common(old, new, name) != position_in_common(new, old, name)
+}
+
+fn find_field<'a>(fields: &[(&str, &'a Shape)], name: &str) -> Option<&'a Shape> {
+    fields
+        .iter()
+        .find(|(field, _)| *field == name)
+        .map(|(_, shape)| *shape)
+}
+
+fn is_option(shape: &Shape) -> bool {
+    matches!(shape, Shape::Opaque { name, .. } if name == "std::option::Option")
+}
+
+/// Displays the reason on one line, such as `Foo.b: removed: ...`
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.0.is_empty() {
+            write!(f, "{} ({:?})", self.description, self.compatibility)
+        } else {
+            write!(
+                f,
+                "{}: {} ({:?})",
+                self.path, self.description, self.compatibility
+            )
+        }
+    }
+}
@@ -0,0 +1,726 @@
+//! Find the differences between two shapes.
+//!
+//! When the hash of a type changes, [`diff`] of its old and new [`Shape`]s says which
+//! fields, variants and types changed:
+//!
+//! ```text
+//! Wibble::C.foo.a: i64 -> u64
+//! Bar::B discriminant 2 -> 3
+//! Foo.b removed
+//! ```
+use crate::shape::{ConstArg, Field, Fields, Method, Shape, Variant};
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::mem;
+
+/// A difference between two shapes, at a path within them
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    pub path: Path,
+    pub kind: ChangeKind,
+}
+
+/// What changed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// The type was replaced by a different type
+    Type { old: Shape, new: Shape },
+    /// A struct or variant changed between having named fields, unnamed fields or none
+    Fields { old: FieldsKind, new: FieldsKind },
+    /// A field was added, with this shape
+    FieldAdded(Shape),
+    /// A field was removed. This was its shape.
+    FieldRemoved(Shape),
+    /// A variant was added
+    VariantAdded(Variant),
+    /// A variant was removed
+    VariantRemoved(Variant),
+    /// A named field or variant changed position
+    Moved { old: usize, new: usize },
+    /// The discriminant of a variant changed
+    Discriminant {
+        old: Option<i128>,
+        new: Option<i128>,
+    },
+    /// The hasher of a `HashSet` or `HashMap` changed, or started or stopped being part
+    /// of the hash
+    Hasher {
+        old: Option<Shape>,
+        new: Option<Shape>,
+    },
+    /// The bytes that the `#[type_hash(with = "...")]` function of a field writes
+    /// changed. The name is how the field's type is displayed.
+    Custom {
+        name: String,
+        old: Vec<u8>,
+        new: Vec<u8>,
+    },
+    /// The values of a type's const generic arguments changed
+    ConstArgs {
+        old: Vec<ConstArg>,
+        new: Vec<ConstArg>,
+    },
+    /// A method was added to a trait
+    MethodAdded(Method),
+    /// A method was removed from a trait
+    MethodRemoved(Method),
+    /// The receiver of a method, whether it is `unsafe` or its number of arguments
+    /// changed
+    Signature { old: Method, new: Method },
+}
+
+/// The kind of fields that a struct or variant has
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldsKind {
+    Unit,
+    Named,
+    Unnamed,
+}
+
+/// Where a change is, starting from the type that was compared
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Path(pub Vec<Segment>);
+
+/// A step along a [`Path`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Segment {
+    /// The named type that was compared, written as `Foo`
+    Type(String),
+    /// A variant of an enum, written as `::A`
+    Variant(String),
+    /// A method of a trait, written as `::a`
+    Method(String),
+    /// A supertrait of a trait, written as `: A`
+    Supertrait(String),
+    /// A named field, written as `.a`
+    Field(String),
+    /// An unnamed field or tuple element, written as `.0`
+    Index(usize),
+    /// The element of a sequence, array or slice, written as `[_]`
+    Element,
+    /// The key of a map, written as `[key]`
+    Key,
+    /// The value of a map, written as `[value]`
+    Value,
+    /// A type argument of an opaque type, written as `<0>`
+    Arg(usize),
+    /// An argument of a function pointer or method, written as `(0)`
+    Param(usize),
+    /// What a function pointer or method returns, written as `(output)`
+    Output,
+}
+
+/// Find the differences between two shapes, which are usually two versions of the same
+/// type.
+///
+/// Changes inside a type that is used in several places are reported at each place.
+/// The result is empty exactly when the shapes are equal, apart from the names of
+/// [`Shape::Custom`]s and [`Shape::BackRef`]s, which are only for display.
+pub fn diff(old: &Shape, new: &Shape) -> Vec<Change> {
+    let mut path = Path::default();
+    if let (Some(old_name), Some(new_name)) = (old.type_name(), new.type_name()) {
+        if old_name == new_name && mem::discriminant(old) == mem::discriminant(new) {
+            path.0.push(Segment::Type(old_name.to_owned()));
+        }
+    }
+    let mut changes = Vec::new();
+    diff_shapes(old, new, &mut path, &mut changes);
+    changes
+}
+
+fn diff_shapes(old: &Shape, new: &Shape, path: &mut Path, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (
+            Shape::Struct {
+                name: old_name,
+                fields: old_fields,
+            },
+            Shape::Struct {
+                name: new_name,
+                fields: new_fields,
+            },
+        ) if old_name == new_name => diff_fields(old_fields, new_fields, path, changes),
+        (
+            Shape::Union {
+                name: old_name,
+                fields: old_fields,
+            },
+            Shape::Union {
+                name: new_name,
+                fields: new_fields,
+            },
+        ) if old_name == new_name => diff_named(old_fields, new_fields, path, changes),
+        (
+            Shape::Enum {
+                name: old_name,
+                variants: old_variants,
+            },
+            Shape::Enum {
+                name: new_name,
+                variants: new_variants,
+            },
+        ) if old_name == new_name => diff_named(old_variants, new_variants, path, changes),
+        (Shape::Tuple(old_elements), Shape::Tuple(new_elements))
+            if old_elements.len() == new_elements.len() =>
+        {
+            for (index, (old, new)) in old_elements.iter().zip(new_elements).enumerate() {
+                within(path, Segment::Index(index), |path| {
+                    diff_shapes(old, new, path, changes)
+                });
+            }
+        }
+        (
+            Shape::Sequence {
+                name: old_name,
+                element: old_element,
+                hasher: old_hasher,
+            },
+            Shape::Sequence {
+                name: new_name,
+                element: new_element,
+                hasher: new_hasher,
+            },
+        ) if old_name == new_name => {
+            diff_hashers(old_hasher, new_hasher, path, changes);
+            within(path, Segment::Element, |path| {
+                diff_shapes(old_element, new_element, path, changes)
+            })
+        }
+        (
+            Shape::Map {
+                name: old_name,
+                key: old_key,
+                value: old_value,
+                hasher: old_hasher,
+            },
+            Shape::Map {
+                name: new_name,
+                key: new_key,
+                value: new_value,
+                hasher: new_hasher,
+            },
+        ) if old_name == new_name => {
+            diff_hashers(old_hasher, new_hasher, path, changes);
+            within(path, Segment::Key, |path| {
+                diff_shapes(old_key, new_key, path, changes)
+            });
+            within(path, Segment::Value, |path| {
+                diff_shapes(old_value, new_value, path, changes)
+            });
+        }
+        (
+            Shape::Array {
+                len: old_len,
+                element: old_element,
+            },
+            Shape::Array {
+                len: new_len,
+                element: new_element,
+            },
+        ) if old_len == new_len => within(path, Segment::Element, |path| {
+            diff_shapes(old_element, new_element, path, changes)
+        }),
+        (Shape::Slice(old_element), Shape::Slice(new_element)) => {
+            within(path, Segment::Element, |path| {
+                diff_shapes(old_element, new_element, path, changes)
+            })
+        }
+        (
+            Shape::Reference {
+                mutable: old_mutable,
+                target: old_target,
+            },
+            Shape::Reference {
+                mutable: new_mutable,
+                target: new_target,
+            },
+        )
+        | (
+            Shape::Pointer {
+                mutable: old_mutable,
+                target: old_target,
+            },
+            Shape::Pointer {
+                mutable: new_mutable,
+                target: new_target,
+            },
+        ) if old_mutable == new_mutable => diff_shapes(old_target, new_target, path, changes),
+        (
+            Shape::Opaque {
+                name: old_name,
+                args: old_args,
+            },
+            Shape::Opaque {
+                name: new_name,
+                args: new_args,
+            },
+        ) if old_name == new_name && old_args.len() == new_args.len() => {
+            for (index, (old, new)) in old_args.iter().zip(new_args).enumerate() {
+                within(path, Segment::Arg(index), |path| {
+                    diff_shapes(old, new, path, changes)
+                });
+            }
+        }
+        (
+            Shape::Function {
+                abi: old_abi,
+                is_unsafe: old_unsafe,
+                args: old_args,
+                output: old_output,
+            },
+            Shape::Function {
+                abi: new_abi,
+                is_unsafe: new_unsafe,
+                args: new_args,
+                output: new_output,
+            },
+        ) if old_abi == new_abi && old_unsafe == new_unsafe && old_args.len() == new_args.len() => {
+            for (index, (old, new)) in old_args.iter().zip(new_args).enumerate() {
+                within(path, Segment::Param(index), |path| {
+                    diff_shapes(old, new, path, changes)
+                });
+            }
+            within(path, Segment::Output, |path| {
+                diff_shapes(old_output, new_output, path, changes)
+            });
+        }
+        (
+            Shape::ConstArgs {
+                args: old_args,
+                shape: old_shape,
+            },
+            Shape::ConstArgs {
+                args: new_args,
+                shape: new_shape,
+            },
+        ) => {
+            if old_args != new_args {
+                changes.push(Change {
+                    path: path.clone(),
+                    kind: ChangeKind::ConstArgs {
+                        old: old_args.clone(),
+                        new: new_args.clone(),
+                    },
+                });
+            }
+            diff_shapes(old_shape, new_shape, path, changes);
+        }
+        (
+            Shape::Trait {
+                name: old_name,
+                supertraits: old_supertraits,
+                methods: old_methods,
+                auto_traits: old_auto_traits,
+            },
+            Shape::Trait {
+                name: new_name,
+                supertraits: new_supertraits,
+                methods: new_methods,
+                auto_traits: new_auto_traits,
+            },
+        ) if old_name == new_name
+            && old_supertraits.len() == new_supertraits.len()
+            && old_auto_traits == new_auto_traits =>
+        {
+            for (old, new) in old_supertraits.iter().zip(new_supertraits) {
+                let name = match old.type_name() {
+                    Some(name) => name.to_owned(),
+                    None => old.to_string(),
+                };
+                within(path, Segment::Supertrait(name), |path| {
+                    diff_shapes(old, new, path, changes)
+                });
+            }
+            diff_named(old_methods, new_methods, path, changes)
+        }
+        (
+            Shape::Custom {
+                bytes: old_bytes, ..
+            },
+            Shape::Custom {
+                bytes: new_bytes, ..
+            },
+        ) if old_bytes == new_bytes => {}
+        (
+            Shape::Custom {
+                bytes: old_bytes, ..
+            },
+            Shape::Custom {
+                bytes: new_bytes, ..
+            },
+        ) => changes.push(Change {
+            path: path.clone(),
+            kind: ChangeKind::Custom {
+                name: new.to_string(),
+                old: old_bytes.clone(),
+                new: new_bytes.clone(),
+            },
+        }),
+        (
+            Shape::BackRef {
+                depth: old_depth, ..
+            },
+            Shape::BackRef {
+                depth: new_depth, ..
+            },
+        ) if old_depth == new_depth => {}
+        _ if old == new => {}
+        _ => changes.push(Change {
+            path: path.clone(),
+            kind: ChangeKind::Type {
+                old: old.clone(),
+                new: new.clone(),
+            },
+        }),
+    }
+}
+
+fn diff_hashers(
+    old: &Option<Box<Shape>>,
+    new: &Option<Box<Shape>>,
+    path: &mut Path,
+    changes: &mut Vec<Change>,
+) {
+    if old != new {
+        changes.push(Change {
+            path: path.clone(),
+            kind: ChangeKind::Hasher {
+                old: old.as_deref().cloned(),
+                new: new.as_deref().cloned(),
+            },
+        });
+    }
+}
+
+fn diff_fields(old: &Fields, new: &Fields, path: &mut Path, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (Fields::Unit, Fields::Unit) => {}
+        (Fields::Named(old), Fields::Named(new)) => diff_named(old, new, path, changes),
+        (Fields::Unnamed(old), Fields::Unnamed(new)) => {
+            for index in 0..old.len().max(new.len()) {
+                within(path, Segment::Index(index), |path| {
+                    match (old.get(index), new.get(index)) {
+                        (Some(old), Some(new)) => diff_shapes(old, new, path, changes),
+                        (Some(old), None) => changes.push(Change {
+                            path: path.clone(),
+                            kind: ChangeKind::FieldRemoved(old.clone()),
+                        }),
+                        (None, Some(new)) => changes.push(Change {
+                            path: path.clone(),
+                            kind: ChangeKind::FieldAdded(new.clone()),
+                        }),
+                        (None, None) => unreachable!(),
+                    }
+                });
+            }
+        }
+        _ => changes.push(Change {
+            path: path.clone(),
+            kind: ChangeKind::Fields {
+                old: FieldsKind::of(old),
+                new: FieldsKind::of(new),
+            },
+        }),
+    }
+}
+
+/// Named fields and variants, which are matched up by name
+trait Named {
+    fn name(&self) -> &str;
+    fn segment(&self) -> Segment;
+    fn added(&self) -> ChangeKind;
+    fn removed(&self) -> ChangeKind;
+    fn diff(old: &Self, new: &Self, path: &mut Path, changes: &mut Vec<Change>);
+}
+
+impl Named for Field {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn segment(&self) -> Segment {
+        Segment::Field(self.name.clone())
+    }
+
+    fn added(&self) -> ChangeKind {
+        ChangeKind::FieldAdded(self.shape.clone())
+    }
+
+    fn removed(&self) -> ChangeKind {
+        ChangeKind::FieldRemoved(self.shape.clone())
+    }
+
+    fn diff(old: &Self, new: &Self, path: &mut Path, changes: &mut Vec<Change>) {
+        diff_shapes(&old.shape, &new.shape, path, changes);
+    }
+}
+
+impl Named for Variant {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn segment(&self) -> Segment {
+        Segment::Variant(self.name.clone())
+    }
+
+    fn added(&self) -> ChangeKind {
+        ChangeKind::VariantAdded(self.clone())
+    }
+
+    fn removed(&self) -> ChangeKind {
+        ChangeKind::VariantRemoved(self.clone())
+    }
+
+    fn diff(old: &Self, new: &Self, path: &mut Path, changes: &mut Vec<Change>) {
+        if old.discriminant != new.discriminant {
+            changes.push(Change {
+                path: path.clone(),
+                kind: ChangeKind::Discriminant {
+                    old: old.discriminant,
+                    new: new.discriminant,
+                },
+            });
+        }
+        diff_fields(&old.fields, &new.fields, path, changes);
+    }
+}
+
+impl Named for Method {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn segment(&self) -> Segment {
+        Segment::Method(self.name.clone())
+    }
+
+    fn added(&self) -> ChangeKind {
+        ChangeKind::MethodAdded(self.clone())
+    }
+
+    fn removed(&self) -> ChangeKind {
+        ChangeKind::MethodRemoved(self.clone())
+    }
+
+    fn diff(old: &Self, new: &Self, path: &mut Path, changes: &mut Vec<Change>) {
+        if old.receiver != new.receiver
+            || old.is_unsafe != new.is_unsafe
+            || old.args.len() != new.args.len()
+        {
+            changes.push(Change {
+                path: path.clone(),
+                kind: ChangeKind::Signature {
+                    old: old.clone(),
+                    new: new.clone(),
+                },
+            });
+            return;
+        }
+        for (index, (old, new)) in old.args.iter().zip(&new.args).enumerate() {
+            within(path, Segment::Param(index), |path| {
+                diff_shapes(old, new, path, changes)
+            });
+        }
+        within(path, Segment::Output, |path| {
+            diff_shapes(&old.output, &new.output, path, changes)
+        });
+    }
+}
+
+/// Match up items by name, reporting those that were removed, added or moved, and the
+/// differences between those that are in both.
+fn diff_named<T: Named>(old: &[T], new: &[T], path: &mut Path, changes: &mut Vec<Change>) {
+    let position = |items: &[T], name: &str| items.iter().position(|item| item.name() == name);
+    // Only a change in the order of the items that are in both counts as a move, not a
+    // change of position caused by adding or removing other items
+    let old_common: Vec<_> = old
+        .iter()
+        .filter(|item| position(new, item.name()).is_some())
+        .map(T::name)
+        .collect();
+    let new_common: Vec<_> = new
+        .iter()
+        .filter(|item| position(old, item.name()).is_some())
+        .map(T::name)
+        .collect();
+    for (old_index, old_item) in old.iter().enumerate() {
+        within(path, old_item.segment(), |path| {
+            match position(new, old_item.name()) {
+                Some(new_index) => {
+                    let name = old_item.name();
+                    if position_of(&old_common, name) != position_of(&new_common, name) {
+                        changes.push(Change {
+                            path: path.clone(),
+                            kind: ChangeKind::Moved {
+                                old: old_index,
+                                new: new_index,
+                            },
+                        });
+                    }
+                    T::diff(old_item, &new[new_index], path, changes);
+                }
+                None => changes.push(Change {
+                    path: path.clone(),
+                    kind: old_item.removed(),
+                }),
+            }
+        });
+    }
+    for new_item in new {
+        if position(old, new_item.name()).is_none() {
+            within(path, new_item.segment(), |path| {
+                changes.push(Change {
+                    path: path.clone(),
+                    kind: new_item.added(),
+                })
+            });
+        }
+    }
+}
+
+fn position_of(names: &[&str], name: &str) -> Option<usize> {
+    names.iter().position(|n| *n == name)
+}
+
+/// Run `f` with `segment` added to the end of the path
+fn within<F: FnOnce(&mut Path)>(path: &mut Path, segment: Segment, f: F) {
+    path.0.push(segment);
+    f(path);
+    path.0.pop();
+}
+
+impl FieldsKind {
+    pub(crate) fn of(fields: &Fields) -> FieldsKind {
+        match fields {
+            Fields::Unit => FieldsKind::Unit,
+            Fields::Named(_) => FieldsKind::Named,
+            Fields::Unnamed(_) => FieldsKind::Unnamed,
+        }
+    }
+}
+
+impl fmt::Display for FieldsKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FieldsKind::Unit => "no fields",
+            FieldsKind::Named => "named fields",
+            FieldsKind::Unnamed => "unnamed fields",
+        })
+    }
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Segment::Type(name) => f.write_str(name),
+            Segment::Variant(name) | Segment::Method(name) => write!(f, "::{}", name),
+            Segment::Supertrait(name) => write!(f, ": {}", name),
+            Segment::Field(name) => write!(f, ".{}", name),
+            Segment::Index(index) => write!(f, ".{}", index),
+            Segment::Element => f.write_str("[_]"),
+            Segment::Key => f.write_str("[key]"),
+            Segment::Value => f.write_str("[value]"),
+            Segment::Arg(index) => write!(f, "<{}>", index),
+            Segment::Param(index) => write!(f, "({})", index),
+            Segment::Output => f.write_str("(output)"),
+        }
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0
+            .iter()
+            .try_for_each(|segment| write!(f, "{}", segment))
+    }
+}
+
+/// Displays the change on one line, such as `Foo.a: i64 -> u64`
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = &self.path;
+        let separator = if path.0.is_empty() { "" } else { ": " };
+        match &self.kind {
+            ChangeKind::Type { old, new } => write!(f, "{}{}{} -> {}", path, separator, old, new),
+            ChangeKind::Fields { old, new } => {
+                write!(f, "{}{}{} -> {}", path, separator, old, new)
+            }
+            ChangeKind::FieldAdded(_)
+            | ChangeKind::VariantAdded(_)
+            | ChangeKind::MethodAdded(_) => {
+                write!(f, "{} added", path)
+            }
+            ChangeKind::FieldRemoved(_)
+            | ChangeKind::VariantRemoved(_)
+            | ChangeKind::MethodRemoved(_) => write!(f, "{} removed", path),
+            ChangeKind::Signature { old, new } => write!(f, "{}: {} -> {}", path, old, new),
+            ChangeKind::Moved { old, new } => write!(f, "{} moved {} -> {}", path, old, new),
+            ChangeKind::Discriminant { old, new } => write!(
+                f,
+                "{} discriminant {} -> {}",
+                path,
+                DisplayDiscriminant(*old),
+                DisplayDiscriminant(*new)
+            ),
+            ChangeKind::Hasher { old, new } => write!(
+                f,
+                "{} hasher {} -> {}",
+                path,
+                DisplayHasher(old.as_ref()),
+                DisplayHasher(new.as_ref())
+            ),
+            ChangeKind::Custom { name, .. } => write!(
+                f,
+                "{}{}custom encoding of `{}` changed",
+                path, separator, name
+            ),
+            ChangeKind::ConstArgs { old, new } => write!(
+                f,
+                "{} const arguments <{}> -> <{}>",
+                path,
+                DisplayConstArgs(old),
+                DisplayConstArgs(new)
+            ),
+        }
+    }
+}
+
+struct DisplayDiscriminant(Option<i128>);
+
+impl fmt::Display for DisplayDiscriminant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(discriminant) => write!(f, "{}", discriminant),
+            None => f.write_str("none"),
+        }
+    }
+}
+
+pub(crate) struct DisplayHasher<'a>(pub(crate) Option<&'a Shape>);
+
+impl fmt::Display for DisplayHasher<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(hasher) => write!(f, "{}", hasher),
+            None => f.write_str("none"),
+        }
+    }
+}
+
+struct DisplayConstArgs<'a>(&'a [ConstArg]);
+
+impl fmt::Display for DisplayConstArgs<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, arg) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", arg)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,272 @@
+//! The encoding of a type's structure that is written to the hasher.
+//!
+//! Every item starts with a one byte [`Tag`], which says what kind of item it is, and
+//! every string and list is prefixed with its length. This means that no two different
+//! type structures are written as the same sequence of bytes.
+//!
+//! The items are:
+//!
+//! | Item                      | Encoding                                               |
+//! |---------------------------|--------------------------------------------------------|
+//! | primitive                 | `Primitive` name                                       |
+//! | struct                    | `Struct` name len(fields) (`Field` name type)*         |
+//! | tuple struct              | `TupleStruct` name len(fields) (`Field` type)*         |
+//! | unit struct               | `UnitStruct` name                                      |
+//! | union                     | `Union` name len(fields) (`Field` name type)*          |
+//! | enum                      | `Enum` name len(variants) variant*                     |
+//! | variant                   | `Variant` index name discriminant? fields              |
+//! | discriminant              | `Discriminant` value                                   |
+//! | tuple                     | `Tuple` len(elements) element*                         |
+//! | sequence                  | `Sequence` name element hasher?                        |
+//! | map                       | `Map` name key value hasher?                           |
+//! | hasher                    | `Hasher` type                                          |
+//! | array                     | `Array` length element                                 |
+//! | slice                     | `Slice` element                                        |
+//! | `*const T` / `*mut T`     | `ConstPointer` T / `MutPointer` T                      |
+//! | `&T` / `&mut T`           | `Reference` T / `MutReference` T                       |
+//! | function pointer          | `Function` abi len(args) arg* output                   |
+//! | `unsafe` function pointer | `UnsafeFunction` abi len(args) arg* output             |
+//! | trait object              | `Trait` name len(supertraits) supertrait*              |
+//! |                           | len(methods) method* len(auto_traits) auto_trait*      |
+//! | method                    | `Method` name receiver unsafe len(args) arg* output    |
+//! | foreign type              | `Foreign` name                                         |
+//! | custom type               | `Custom` len(bytes) bytes                              |
+//! | opaque type               | `Opaque` name len(args) arg*                           |
+//! | const arguments           | `ConstArgs` len(args) arg* type                        |
+//! | const argument            | `Int` value / `Bool` value / `Char` value              |
+//! | back-reference            | `BackRef` depth                                        |
+//!
+//! The fields of a variant are written in the same way as those of a struct, starting
+//! with the `Struct`, `TupleStruct` or `UnitStruct` tag, but without a name.
+//!
+//! Primitives are types that are built in to the language. Sequences, maps and opaque
+//! types are from the standard library, and are named by their full path, such as
+//! `std::vec::Vec`. A name is written as a length followed by its UTF-8 bytes. The
+//! hasher of a `HashSet` or `HashMap` is only written for a field marked
+//! `#[type_hash(hasher)]`.
+//!
+//! A supertrait is written as the trait object of the supertrait if it is also a
+//! `#[trait_hash]` trait, or as a foreign type named by the trait's name if it is from
+//! the standard library, or by the lifetime.
+//!
+//! The receiver of a method is written as one of `self`, `&self`, `&mut self`,
+//! `self: Box<Self>`, `self: Rc<Self>`, `self: Arc<Self>` and `self: Pin<&mut Self>`,
+//! or as an empty name if it has none, and whether the method is `unsafe` is written in
+//! the same way as a `bool` const argument.
+//!
+//! Lengths, counts, indices and back-reference depths are written as 8 byte
+//! little-endian unsigned integers and discriminants are written as 16 byte
+//! little-endian signed integers. Const arguments are written in the same way as
+//! discriminants if they are integers, as one byte if they are `bool`s and as 4 byte
+//! little-endian code points if they are `char`s. Nothing is written with a native byte order or
+//! width, so a type has the same hash on every target.
+//!
+//! A `u128` discriminant above `i128::MAX` can't be written, so deriving `TypeHash` for
+//! its enum is a compile error.
+use crate::shape::ConstArg;
+use crate::TypeHash;
+use core::any::type_name;
+use core::hash::Hasher;
+
+/// Identifies the kind of each item in the encoding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Tag {
+    Primitive = 1,
+    Struct = 2,
+    TupleStruct = 3,
+    UnitStruct = 4,
+    Enum = 5,
+    Variant = 6,
+    Discriminant = 7,
+    Field = 8,
+    Tuple = 9,
+    Array = 10,
+    Slice = 11,
+    ConstPointer = 12,
+    MutPointer = 13,
+    Reference = 14,
+    MutReference = 15,
+    Foreign = 16,
+    BackRef = 17,
+    Sequence = 18,
+    Map = 19,
+    Opaque = 20,
+    Union = 21,
+    ConstArgs = 22,
+    Int = 23,
+    Bool = 24,
+    Char = 25,
+    Function = 26,
+    UnsafeFunction = 27,
+    Trait = 28,
+    Method = 29,
+    Custom = 30,
+    Hasher = 31,
+}
+
+/// Write a tag
+pub fn write_tag(hasher: &mut impl Hasher, tag: Tag) {
+    hasher.write(&[tag as u8]);
+}
+
+/// Write a length, count or index
+pub fn write_len(hasher: &mut impl Hasher, len: usize) {
+    hasher.write(&(len as u64).to_le_bytes());
+}
+
+/// Write a string, prefixed with its length
+pub fn write_str(hasher: &mut impl Hasher, s: &str) {
+    write_len(hasher, s.len());
+    hasher.write(s.as_bytes());
+}
+
+/// Write the value of an enum discriminant
+pub fn write_discriminant(hasher: &mut impl Hasher, discriminant: i128) {
+    write_tag(hasher, Tag::Discriminant);
+    hasher.write(&discriminant.to_le_bytes());
+}
+
+/// Write the value of a const generic argument
+pub fn write_const_arg(hasher: &mut impl Hasher, arg: ConstArg) {
+    match arg {
+        ConstArg::Int(value) => {
+            write_tag(hasher, Tag::Int);
+            hasher.write(&value.to_le_bytes());
+        }
+        ConstArg::Bool(value) => {
+            write_tag(hasher, Tag::Bool);
+            hasher.write(&[value as u8]);
+        }
+        ConstArg::Char(value) => {
+            write_tag(hasher, Tag::Char);
+            hasher.write(&(value as u32).to_le_bytes());
+        }
+    }
+}
+
+/// A part of the encoding of a type, in a form that can be built and hashed in a const
+/// context.
+///
+/// The encoding of a type is a slice of pieces, where [`Piece::Type`] holds the
+/// encoding of a type that it contains. Writing the pieces in order, with
+/// [`write_pieces`], writes the same bytes as [`Shape::write_hash`] does for the shape
+/// of any type that is not recursive.
+///
+/// [`Shape::write_hash`]: crate::shape::Shape::write_hash
+#[derive(Clone, Copy, Debug)]
+pub enum Piece {
+    Tag(Tag),
+    Len(usize),
+    Str(&'static str),
+    Discriminant(i128),
+    Const(ConstArg),
+    Type(&'static [Piece]),
+}
+
+/// Write the encoding of a type, given as pieces
+pub fn write_pieces(hasher: &mut impl Hasher, pieces: &[Piece]) {
+    for piece in pieces {
+        match *piece {
+            Piece::Tag(tag) => write_tag(hasher, tag),
+            Piece::Len(len) => write_len(hasher, len),
+            Piece::Str(s) => write_str(hasher, s),
+            Piece::Discriminant(discriminant) => write_discriminant(hasher, discriminant),
+            Piece::Const(arg) => write_const_arg(hasher, arg),
+            Piece::Type(pieces) => write_pieces(hasher, pieces),
+        }
+    }
+}
+
+/// Converts a `u128` discriminant or const argument to the `i128` that is written, for
+/// the derived implementations. Using [`U128::I128`] for a value above `i128::MAX` is a
+/// compile error.
+#[doc(hidden)]
+pub struct U128<const VALUE: u128>;
+
+impl<const VALUE: u128> U128<VALUE> {
+    pub const I128: i128 = {
+        assert!(
+            VALUE <= i128::MAX as u128,
+            "Values above `i128::MAX` can't be hashed"
+        );
+        VALUE as i128
+    };
+}
+
+/// The encoding of a type that is only known at runtime, which is a field marked
+/// `#[type_hash(with = "...")]`. Evaluating it is a compile error, so such types have
+/// no [`TYPE_HASH`](crate::TypeHash::TYPE_HASH).
+pub const fn runtime_only() -> &'static [Piece] {
+    panic!(
+        "The hash of a type with a `#[type_hash(with = \"...\")]` field is only known at runtime"
+    )
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// The 64 bit FNV-1a hash of the encoding of a type, given as pieces.
+///
+/// This is the same as the hash that [`TypeHash::type_hash`] computes at runtime.
+///
+/// [`TypeHash::type_hash`]: crate::TypeHash::type_hash
+pub const fn fnv_hash(pieces: &[Piece]) -> u64 {
+    fnv_pieces(FNV_OFFSET_BASIS, pieces)
+}
+
+/// Panics if the [`TYPE_HASH`](crate::TypeHash::TYPE_HASH) of `T`, which is computed
+/// from its `ENCODING`, is not the same as the hash of its shape.
+///
+/// The derived implementations always agree, but nothing else checks a hand-written
+/// implementation, so call this from a test for each one. It can't be used for recursive
+/// types, whose `ENCODING` can't be evaluated.
+pub fn assert_encoding_matches<T: TypeHash + ?Sized>() {
+    let shape = T::shape();
+    assert!(
+        T::TYPE_HASH == shape.type_hash(),
+        "the `ENCODING` of `{}` does not match its shape `{}`",
+        type_name::<T>(),
+        shape
+    );
+}
+
+const fn fnv_pieces(mut hash: u64, pieces: &[Piece]) -> u64 {
+    let mut i = 0;
+    while i < pieces.len() {
+        hash = match pieces[i] {
+            Piece::Tag(tag) => fnv_bytes(hash, &[tag as u8]),
+            Piece::Len(len) => fnv_bytes(hash, &(len as u64).to_le_bytes()),
+            Piece::Str(s) => {
+                let hash = fnv_bytes(hash, &(s.len() as u64).to_le_bytes());
+                fnv_bytes(hash, s.as_bytes())
+            }
+            Piece::Discriminant(discriminant) => {
+                let hash = fnv_bytes(hash, &[Tag::Discriminant as u8]);
+                fnv_bytes(hash, &discriminant.to_le_bytes())
+            }
+            Piece::Const(ConstArg::Int(value)) => {
+                let hash = fnv_bytes(hash, &[Tag::Int as u8]);
+                fnv_bytes(hash, &value.to_le_bytes())
+            }
+            Piece::Const(ConstArg::Bool(value)) => fnv_bytes(hash, &[Tag::Bool as u8, value as u8]),
+            Piece::Const(ConstArg::Char(value)) => {
+                let hash = fnv_bytes(hash, &[Tag::Char as u8]);
+                fnv_bytes(hash, &(value as u32).to_le_bytes())
+            }
+            Piece::Type(pieces) => fnv_pieces(hash, pieces),
+        };
+        i += 1;
+    }
+    hash
+}
+
+const fn fnv_bytes(mut hash: u64, bytes: &[u8]) -> u64 {
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+        i += 1;
+    }
+    hash
+}
@@ -0,0 +1,146 @@
+//! Hashers for the wider hashes of [`TypeHash`](crate::TypeHash).
+use alloc::vec::Vec;
+use core::hash::Hasher;
+
+const FNV_128_OFFSET_BASIS: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+const FNV_128_PRIME: u128 = 0x0000_0000_0100_0000_0000_0000_0000_013b;
+
+/// The 128 bit FNV-1a hash.
+///
+/// [`Hasher::finish`] can only return 64 bits, so it returns the low 64 bits of the hash.
+/// Use [`Fnv128Hasher::finish_128`] for the full hash.
+#[derive(Clone, Copy, Debug)]
+pub struct Fnv128Hasher(u128);
+
+impl Default for Fnv128Hasher {
+    fn default() -> Self {
+        Fnv128Hasher(FNV_128_OFFSET_BASIS)
+    }
+}
+
+impl Fnv128Hasher {
+    /// The 128 bit hash of the bytes written so far
+    pub fn finish_128(&self) -> u128 {
+        self.0
+    }
+}
+
+impl Hasher for Fnv128Hasher {
+    fn finish(&self) -> u64 {
+        self.0 as u64
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u128::from(*byte);
+            self.0 = self.0.wrapping_mul(FNV_128_PRIME);
+        }
+    }
+}
+
+/// Adapts a cryptographic [`Digest`](sha2::Digest) so that a type's structure can be
+/// written to it.
+///
+/// [`Hasher::finish`] returns the first 8 bytes of the digest, as a little-endian
+/// integer. Use [`DigestHasher::finalize`] for the whole digest.
+#[cfg(feature = "sha2")]
+#[derive(Clone, Debug, Default)]
+pub struct DigestHasher<D>(D);
+
+#[cfg(feature = "sha2")]
+impl<D: sha2::Digest> DigestHasher<D> {
+    /// The digest of the bytes written so far
+    pub fn finalize(self) -> sha2::digest::Output<D> {
+        self.0.finalize()
+    }
+}
+
+#[cfg(feature = "sha2")]
+impl<D: sha2::Digest + Clone> Hasher for DigestHasher<D> {
+    fn finish(&self) -> u64 {
+        let digest = self.0.clone().finalize();
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(&digest[..8]);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+}
+
+/// Collects the bytes that are written to it, which describe a field marked
+/// `#[type_hash(with = "...")]`.
+///
+/// Integers are written as little-endian bytes, and `usize` and `isize` are widened to 8
+/// bytes, so the bytes are the same on every target. [`Hasher::finish`] returns the 64
+/// bit FNV-1a hash of the bytes.
+#[derive(Clone, Debug, Default)]
+pub struct BytesHasher(Vec<u8>);
+
+impl BytesHasher {
+    /// The bytes written so far
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl Hasher for BytesHasher {
+    fn finish(&self) -> u64 {
+        let mut hasher = fnv::FnvHasher::default();
+        hasher.write(&self.0);
+        hasher.finish()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.write(&[i]);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i8(&mut self, i: i8) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_i64(i as i64);
+    }
+}
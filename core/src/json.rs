@@ -0,0 +1,118 @@
+//! A JSON format for storing shapes.
+//!
+//! A stored shape can be compared with the current version of a type using
+//! [`diff`](crate::diff::diff), and its hash can be recomputed with
+//! [`Shape::type_hash`], without the definition of the type that it describes:
+//!
+//! ```ignore
+//! let stored = type_hash::json::from_str(&std::fs::read_to_string("message.json")?)?;
+//! assert_eq!(stored.type_hash(), Message::type_hash());
+//! ```
+//!
+//! A document is an object with the format `version` and the `shape`:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "shape": {
+//!     "struct": {
+//!       "name": "Point",
+//!       "fields": {
+//!         "named": [
+//!           { "name": "x", "shape": { "primitive": "i32" } },
+//!           { "name": "y", "shape": { "primitive": "i32" } }
+//!         ]
+//!       }
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! Every [`Shape`] is an object with a single key, which is the name of its variant in
+//! snake case. Documents written by an older version of this crate can always be read by
+//! a newer one.
+use crate::shape::Shape;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The version of the format that is written
+pub const VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct DocumentRef<'a> {
+    version: u32,
+    shape: &'a Shape,
+}
+
+#[derive(Deserialize)]
+struct Version {
+    version: u32,
+}
+
+#[derive(Deserialize)]
+struct Document {
+    shape: Shape,
+}
+
+/// Write a shape as a JSON document
+pub fn to_string(shape: &Shape) -> String {
+    let document = DocumentRef {
+        version: VERSION,
+        shape,
+    };
+    serde_json::to_string_pretty(&document).expect("a shape can always be serialized")
+}
+
+/// Read a shape from a JSON document
+pub fn from_str(json: &str) -> Result<Shape, Error> {
+    read::<Document>(json).map(|document| document.shape)
+}
+
+/// Read a versioned document
+pub(crate) fn read<T: DeserializeOwned>(json: &str) -> Result<T, Error> {
+    // The version is read first, so that the rest of the document is only read if its
+    // format is known
+    let Version { version } = serde_json::from_str(json).map_err(Error::Json)?;
+    if version != VERSION {
+        return Err(Error::UnsupportedVersion(version));
+    }
+    serde_json::from_str(json).map_err(Error::Json)
+}
+
+/// The 64 bit FNV-1a hash of the shape in a JSON document. This is the same as
+/// [`TypeHash::type_hash`](crate::TypeHash::type_hash) for the type that it describes.
+pub fn type_hash(json: &str) -> Result<u64, Error> {
+    from_str(json).map(|shape| shape.type_hash())
+}
+
+/// An error reading a JSON document
+#[derive(Debug)]
+pub enum Error {
+    /// The document is not valid JSON, or does not describe a shape
+    Json(serde_json::Error),
+    /// The document was written in a newer version of the format
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Json(e) => write!(f, "invalid shape document: {}", e),
+            Error::UnsupportedVersion(version) => write!(
+                f,
+                "shape document has version {}, but only version {} is supported",
+                version, VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Json(e) => Some(e),
+            Error::UnsupportedVersion(_) => None,
+        }
+    }
+}
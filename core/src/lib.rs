@@ -1,27 +1,341 @@
-use std::borrow::ToOwned;
-use std::hash::Hasher;
+//! The traits and types of the [type_hash](https://docs.rs/type_hash) crate.
+//!
+//! This crate is `no_std`, but needs an allocator. The `std` feature, which is enabled
+//! by default, adds implementations for types from `std`, such as `HashMap`, as well as
+//! the `snapshot` module. The `json` and `lock` modules need the `serde` feature and the
+//! `registry` module needs the `registry` feature, which both enable `std`.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(not(feature = "alloc"))]
+compile_error!("type_hash needs an allocator, so the `alloc` feature must be enabled");
+
+pub mod compat;
+pub mod diff;
+pub mod encoding;
+pub mod hasher;
+#[cfg(feature = "serde")]
+pub mod json;
+#[cfg(feature = "serde")]
+pub mod lock;
+#[cfg(feature = "registry")]
+pub mod registry;
+pub mod shape;
+#[cfg(feature = "std")]
+pub mod snapshot;
+
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::any::type_name;
+use core::hash::Hasher;
+use encoding::{Piece, Tag};
+use shape::{ConstArg, Method, Shape};
 
 /// A hash of a type's structure
-pub trait TypeHash {
+pub trait TypeHash: TypeEncoding {
+    /// The hash of the type's structure, computed at compile time.
+    ///
+    /// This is the same as [`TypeHash::type_hash`], but it can't be evaluated for
+    /// recursive types. See [`TypeEncoding`].
+    const TYPE_HASH: u64 = encoding::fnv_hash(<Self as TypeEncoding>::ENCODING);
+
+    /// The 64 bit FNV-1a hash of the type's structure
     fn type_hash() -> u64 {
-        let mut hasher = fnv::FnvHasher::default();
-        Self::write_hash(&mut hasher);
-        hasher.finish()
+        Self::shape().type_hash()
+    }
+
+    /// The hash of the type's structure, computed with the hasher `H`
+    fn type_hash_with<H: Hasher + Default>() -> u64 {
+        Self::shape().type_hash_with::<H>()
+    }
+
+    /// The 128 bit FNV-1a hash of the type's structure.
+    ///
+    /// This is less likely than [`TypeHash::type_hash`] to give the same hash for two
+    /// different types.
+    fn type_hash_128() -> u128 {
+        Self::shape().type_hash_128()
+    }
+
+    /// The SHA-256 digest of the type's structure
+    #[cfg(feature = "sha2")]
+    fn type_hash_sha256() -> [u8; 32] {
+        Self::shape().type_hash_sha256()
     }
 
     /// Write the structure of the type to the hasher
-    fn write_hash(hasher: &mut impl Hasher);
+    fn write_hash(hasher: &mut impl Hasher) {
+        Self::shape().write_hash(hasher);
+    }
+
+    /// The structure of the type. All of the hashes of a type are computed from this.
+    fn shape() -> Shape {
+        Self::shape_within(&Ancestors::Root)
+    }
+
+    /// The structure of the type, where the type is nested inside the types in
+    /// `ancestors`.
+    ///
+    /// Implementations must pass `ancestors` on to the types they contain. Named types
+    /// should use [`Ancestors::enter`] so that recursive types are detected.
+    fn shape_within(ancestors: &Ancestors<'_>) -> Shape;
+}
+
+/// The encoding of a type's structure, as a const value.
+///
+/// This is the same encoding that [`TypeHash::write_hash`] writes, given as [`Piece`]s
+/// so that it can be hashed at compile time.
+///
+/// The type parameter `M` is not used. Implementations should be generic over it, so
+/// that the encoding of a type is only evaluated where it is used. The encoding of a
+/// recursive type would contain itself, so evaluating it is a compile error, but
+/// recursive types can still implement this trait, and so [`TypeHash`].
+///
+/// The encoding must describe the same structure as [`TypeHash::shape_within`]. The
+/// derived implementations build both in one pass over the type, and every built-in
+/// implementation is checked with [`assert_encoding_matches`] in this crate's tests.
+/// Hand-written implementations should be checked in the same way.
+///
+/// [`assert_encoding_matches`]: encoding::assert_encoding_matches
+pub trait TypeEncoding<M: ?Sized = ()> {
+    const ENCODING: &'static [Piece];
+}
+
+/// The chain of types whose shapes are currently being built, innermost first.
+///
+/// This is used to detect when a type refers back to itself, either directly or via
+/// other types, which would otherwise make building its shape recurse forever.
+pub enum Ancestors<'a> {
+    Root,
+    Type {
+        name: &'static str,
+        parent: &'a Ancestors<'a>,
+    },
+}
+
+impl<'a> Ancestors<'a> {
+    /// How many types up the chain the type with this name is, if its shape is being
+    /// built already.
+    pub fn depth_of(&self, name: &str) -> Option<usize> {
+        let mut ancestors = self;
+        let mut depth = 0;
+        while let Ancestors::Type {
+            name: ancestor,
+            parent,
+        } = ancestors
+        {
+            if *ancestor == name {
+                return Some(depth);
+            }
+            ancestors = parent;
+            depth += 1;
+        }
+        None
+    }
+
+    /// Build the shape of the type `T` using `shape`, with `T` added to the ancestors.
+    ///
+    /// If the shape of `T` is already being built then a [`Shape::BackRef`] to it is
+    /// returned instead. The back-reference is relative to the current type, so the
+    /// shape of a recursive type is the same wherever it is built from.
+    pub fn enter<T, F>(&self, shape: F) -> Shape
+    where
+        T: ?Sized,
+        F: FnOnce(&Ancestors<'_>) -> Shape,
+    {
+        let name = type_name::<T>();
+        match self.depth_of(name) {
+            Some(depth) => Shape::BackRef {
+                depth,
+                name: shape::short_type_name(name),
+            },
+            None => shape(&Ancestors::Type { name, parent: self }),
+        }
+    }
+}
+
+/// The full path of a type in `std`, as a string, such as `"std::vec::Vec"`.
+///
+/// Types from `core` and `alloc` are named by where `std` re-exports them, so that they
+/// have the same hash whichever crate they are used from.
+macro_rules! type_path {
+    ($first: ident $(:: $rest: ident)*) => {
+        concat!("std" $(, "::", stringify!($rest))*)
+    };
+}
+
+macro_rules! impl_type_hash_primitive {
+    ($($ty: ident,)*) => {
+        $(
+            impl<M: ?Sized> TypeEncoding<M> for $ty {
+                const ENCODING: &'static [Piece] =
+                    &[Piece::Tag(Tag::Primitive), Piece::Str(stringify!($ty))];
+            }
+
+            impl TypeHash for $ty {
+                fn shape_within(_: &Ancestors<'_>) -> Shape {
+                    Shape::Primitive(stringify!($ty).to_owned())
+                }
+            }
+        )*
+    };
+}
+
+impl_type_hash_primitive!(
+    bool, char, u8, i8, u16, i16, u32, i32, u64, i64, u128, i128, usize, isize, f32, f64, str,
+);
+
+// The hasher of a `HashSet` or `HashMap` is not part of the hash, because it doesn't
+// change how the collection is serialized. See `WithHasher`.
+macro_rules! impl_type_hash_sequence {
+    ($($($ty: ident)::*<T $(, $S: ident)?>,)*) => {
+        $(
+            impl<M: ?Sized, T: TypeEncoding<M> $(, $S)?> TypeEncoding<M> for $($ty)::*<T $(, $S)?> {
+                const ENCODING: &'static [Piece] = &[
+                    Piece::Tag(Tag::Sequence),
+                    Piece::Str(type_path!($($ty)::*)),
+                    Piece::Type(T::ENCODING),
+                ];
+            }
+
+            impl<T: TypeHash $(, $S)?> TypeHash for $($ty)::*<T $(, $S)?> {
+                fn shape_within(ancestors: &Ancestors<'_>) -> Shape {
+                    Shape::Sequence {
+                        name: type_path!($($ty)::*).to_owned(),
+                        element: Box::new(T::shape_within(ancestors)),
+                        hasher: None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_type_hash_sequence!(
+    alloc::collections::BinaryHeap<T>,
+    alloc::collections::BTreeSet<T>,
+    alloc::collections::LinkedList<T>,
+    alloc::collections::VecDeque<T>,
+    alloc::vec::Vec<T>,
+);
+
+#[cfg(feature = "std")]
+impl_type_hash_sequence!(std::collections::HashSet<T, S>,);
+
+macro_rules! impl_type_hash_map {
+    ($($($ty: ident)::*<K, V $(, $S: ident)?>,)*) => {
+        $(
+            impl<M: ?Sized, K: TypeEncoding<M>, V: TypeEncoding<M> $(, $S)?> TypeEncoding<M> for $($ty)::*<K, V $(, $S)?> {
+                const ENCODING: &'static [Piece] = &[
+                    Piece::Tag(Tag::Map),
+                    Piece::Str(type_path!($($ty)::*)),
+                    Piece::Type(K::ENCODING),
+                    Piece::Type(V::ENCODING),
+                ];
+            }
+
+            impl<K: TypeHash, V: TypeHash $(, $S)?> TypeHash for $($ty)::*<K, V $(, $S)?> {
+                fn shape_within(ancestors: &Ancestors<'_>) -> Shape {
+                    Shape::Map {
+                        name: type_path!($($ty)::*).to_owned(),
+                        key: Box::new(K::shape_within(ancestors)),
+                        value: Box::new(V::shape_within(ancestors)),
+                        hasher: None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_type_hash_map!(alloc::collections::BTreeMap<K, V>,);
+
+#[cfg(feature = "std")]
+impl_type_hash_map!(std::collections::HashMap<K, V, S>,);
+
+/// Hashes a `HashMap` or `HashSet` including the type of its hasher, which is otherwise
+/// not part of the hash.
+///
+/// This is used by `#[type_hash(hasher)]` on a field, or can be given to
+/// `#[type_hash(as = "...")]`.
+#[cfg(feature = "std")]
+pub struct WithHasher<T: ?Sized>(core::marker::PhantomData<T>);
+
+#[cfg(feature = "std")]
+impl<M: ?Sized, T: TypeEncoding<M>, S: TypeEncoding<M>> TypeEncoding<M>
+    for WithHasher<std::collections::HashSet<T, S>>
+{
+    const ENCODING: &'static [Piece] = &[
+        Piece::Tag(Tag::Sequence),
+        Piece::Str(type_path!(std::collections::HashSet)),
+        Piece::Type(T::ENCODING),
+        Piece::Tag(Tag::Hasher),
+        Piece::Type(S::ENCODING),
+    ];
+}
+
+#[cfg(feature = "std")]
+impl<T: TypeHash, S: TypeHash> TypeHash for WithHasher<std::collections::HashSet<T, S>> {
+    fn shape_within(ancestors: &Ancestors<'_>) -> Shape {
+        Shape::Sequence {
+            name: type_path!(std::collections::HashSet).to_owned(),
+            element: Box::new(T::shape_within(ancestors)),
+            hasher: Some(Box::new(S::shape_within(ancestors))),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<M: ?Sized, K: TypeEncoding<M>, V: TypeEncoding<M>, S: TypeEncoding<M>> TypeEncoding<M>
+    for WithHasher<std::collections::HashMap<K, V, S>>
+{
+    const ENCODING: &'static [Piece] = &[
+        Piece::Tag(Tag::Map),
+        Piece::Str(type_path!(std::collections::HashMap)),
+        Piece::Type(K::ENCODING),
+        Piece::Type(V::ENCODING),
+        Piece::Tag(Tag::Hasher),
+        Piece::Type(S::ENCODING),
+    ];
+}
+
+#[cfg(feature = "std")]
+impl<K: TypeHash, V: TypeHash, S: TypeHash> TypeHash
+    for WithHasher<std::collections::HashMap<K, V, S>>
+{
+    fn shape_within(ancestors: &Ancestors<'_>) -> Shape {
+        Shape::Map {
+            name: type_path!(std::collections::HashMap).to_owned(),
+            key: Box::new(K::shape_within(ancestors)),
+            value: Box::new(V::shape_within(ancestors)),
+            hasher: Some(Box::new(S::shape_within(ancestors))),
+        }
+    }
 }
 
 macro_rules! impl_type_hash {
+    (@one $T: ident) => { 1 };
     ($( $($ty: ident)::* $(<$($l: lifetime,)* $($T: ident $(: $(? $Sized: ident)? $($(+)? $B: ident)*)?),+>)?,)*) => {
         $(
+            impl<$($($l,)*)? M: ?Sized $($(, $T: TypeEncoding<M> $($(+ ?$Sized)? $(+ $B)*)?)*)?> TypeEncoding<M> for $($ty)::* $(<$($l,)* $($T),+>)? {
+                const ENCODING: &'static [Piece] = &[
+                    Piece::Tag(Tag::Opaque),
+                    Piece::Str(type_path!($($ty)::*)),
+                    Piece::Len(0 $($(+ impl_type_hash!(@one $T))+)?),
+                    $($(Piece::Type($T::ENCODING),)+)?
+                ];
+            }
+
             impl $(<$($l,)* $($T: $crate::TypeHash $($(+ ?$Sized)? $(+ $B)*)? ),*>)? TypeHash for $($ty)::* $(<$($l,)* $($T),+>)? {
-                fn write_hash(hasher: &mut impl std::hash::Hasher) {
-                    hasher.write(stringify!($($ty)::*).as_bytes());
-                    $($(
-                        $T::write_hash(hasher);
-                    )+)?
+                #[allow(unused_variables)]
+                fn shape_within(ancestors: &Ancestors<'_>) -> Shape {
+                    Shape::Opaque {
+                        name: type_path!($($ty)::*).to_owned(),
+                        args: vec![$($($T::shape_within(ancestors)),+)?],
+                    }
                 }
             }
         )*
@@ -29,126 +343,116 @@ macro_rules! impl_type_hash {
 }
 
 impl_type_hash!(
-    bool,
-    u8,
-    i8,
-    u16,
-    i16,
-    u32,
-    i32,
-    u64,
-    i64,
-    u128,
-    i128,
-    usize,
-    isize,
-    f32,
-    f64,
-    str,
-    std::any::TypeId,
-    std::borrow::Cow<'a, T: ?Sized + ToOwned>,
-    std::boxed::Box<T: ?Sized>,
-    std::cell::Cell<T: ?Sized>,
-    std::cell::Ref<'a, T: ?Sized>,
-    std::cell::RefCell<T: ?Sized>,
-    std::cell::RefMut<'a, T>,
-    std::cell::UnsafeCell<T>,
-    std::cmp::Ordering,
-    std::cmp::Reverse<T>,
-    std::collections::BinaryHeap<T>,
-    std::collections::BTreeMap<K, V>,
-    std::collections::BTreeSet<T>,
-    std::collections::HashMap<K, V>,
-    std::collections::HashSet<T>,
-    std::collections::LinkedList<T>,
-    std::collections::VecDeque<T>,
-    std::ffi::c_void,
-    std::ffi::CStr,
-    std::ffi::CString,
+    core::any::TypeId,
+    core::cell::Cell<T: ?Sized>,
+    core::cell::Ref<'a, T: ?Sized>,
+    core::cell::RefCell<T: ?Sized>,
+    core::cell::RefMut<'a, T>,
+    core::cell::UnsafeCell<T>,
+    core::cmp::Ordering,
+    core::cmp::Reverse<T>,
+    core::ffi::c_void,
+    core::ffi::CStr,
+    core::hash::BuildHasherDefault<T>,
+    core::marker::PhantomData<T: ?Sized>,
+    core::mem::ManuallyDrop<T: ?Sized>,
+    core::mem::MaybeUninit<T>,
+    core::net::IpAddr,
+    core::net::Ipv4Addr,
+    core::net::Ipv6Addr,
+    core::net::SocketAddr,
+    core::net::SocketAddrV4,
+    core::net::SocketAddrV6,
+    core::num::FpCategory,
+    core::num::NonZeroI128,
+    core::num::NonZeroI16,
+    core::num::NonZeroI32,
+    core::num::NonZeroI64,
+    core::num::NonZeroI8,
+    core::num::NonZeroIsize,
+    core::num::NonZeroU128,
+    core::num::NonZeroU16,
+    core::num::NonZeroU32,
+    core::num::NonZeroU64,
+    core::num::NonZeroU8,
+    core::num::NonZeroUsize,
+    core::num::Wrapping<T>,
+    core::ops::Bound<T>,
+    core::ops::Range<T>,
+    core::ops::RangeFrom<T>,
+    core::ops::RangeInclusive<T>,
+    core::ops::RangeFull,
+    core::ops::RangeTo<T>,
+    core::ops::RangeToInclusive<T>,
+    core::option::Option<T>,
+    core::pin::Pin<T>,
+    core::ptr::NonNull<T: ?Sized>,
+    core::result::Result<T, E>,
+    core::sync::atomic::AtomicBool,
+    core::sync::atomic::AtomicI16,
+    core::sync::atomic::AtomicI32,
+    core::sync::atomic::AtomicI64,
+    core::sync::atomic::AtomicI8,
+    core::sync::atomic::AtomicIsize,
+    core::sync::atomic::AtomicPtr<T>,
+    core::sync::atomic::AtomicU16,
+    core::sync::atomic::AtomicU32,
+    core::sync::atomic::AtomicU64,
+    core::sync::atomic::AtomicU8,
+    core::sync::atomic::AtomicUsize,
+    core::time::Duration,
+);
+
+impl_type_hash!(
+    alloc::borrow::Cow<'a, T: ?Sized + ToOwned>,
+    alloc::boxed::Box<T: ?Sized>,
+    alloc::ffi::CString,
+    alloc::rc::Rc<T: ?Sized>,
+    alloc::rc::Weak<T: ?Sized>,
+    alloc::string::String,
+    alloc::sync::Arc<T: ?Sized>,
+    alloc::sync::Weak<T: ?Sized>,
+);
+
+#[cfg(feature = "std")]
+impl_type_hash!(
+    std::collections::hash_map::DefaultHasher,
+    std::collections::hash_map::RandomState,
     std::ffi::OsStr,
     std::ffi::OsString,
-    std::hash::BuildHasherDefault<T>,
-    std::marker::PhantomData<T: ?Sized>,
-    std::mem::ManuallyDrop<T: ?Sized>,
-    std::mem::MaybeUninit<T>,
-    std::net::IpAddr,
-    std::net::Ipv4Addr,
-    std::net::Ipv6Addr,
-    std::net::SocketAddr,
-    std::net::SocketAddrV4,
-    std::net::SocketAddrV6,
-    std::num::FpCategory,
-    std::num::NonZeroI128,
-    std::num::NonZeroI16,
-    std::num::NonZeroI32,
-    std::num::NonZeroI64,
-    std::num::NonZeroI8,
-    std::num::NonZeroIsize,
-    std::num::NonZeroU128,
-    std::num::NonZeroU16,
-    std::num::NonZeroU32,
-    std::num::NonZeroU64,
-    std::num::NonZeroU8,
-    std::num::NonZeroUsize,
-    std::num::Wrapping<T>,
-    std::ops::Bound<T>,
-    std::ops::Range<T>,
-    std::ops::RangeFrom<T>,
-    std::ops::RangeInclusive<T>,
-    std::ops::RangeFull,
-    std::ops::RangeTo<T>,
-    std::ops::RangeToInclusive<T>,
-    std::option::Option<T>,
     std::path::Path,
     std::path::PathBuf,
-    std::pin::Pin<T>,
-    std::primitive::char,
-    std::ptr::NonNull<T: ?Sized>,
-    std::rc::Rc<T: ?Sized>,
-    std::rc::Weak<T: ?Sized>,
-    std::result::Result<T, E>,
-    std::string::String,
-    std::sync::atomic::AtomicBool,
-    std::sync::atomic::AtomicI16,
-    std::sync::atomic::AtomicI32,
-    std::sync::atomic::AtomicI64,
-    std::sync::atomic::AtomicI8,
-    std::sync::atomic::AtomicIsize,
-    std::sync::atomic::AtomicPtr<T>,
-    std::sync::atomic::AtomicU16,
-    std::sync::atomic::AtomicU32,
-    std::sync::atomic::AtomicU64,
-    std::sync::atomic::AtomicU8,
-    std::sync::atomic::AtomicUsize,
     std::sync::mpsc::Receiver<T>,
     std::sync::mpsc::Sender<T>,
     std::sync::mpsc::SyncSender<T>,
-    std::sync::Arc<T: ?Sized>,
     std::sync::Mutex<T: ?Sized>,
     std::sync::Once,
     std::sync::RwLock<T: ?Sized>,
     std::sync::RwLockReadGuard<'a, T: ?Sized>,
     std::sync::RwLockWriteGuard<'a, T: ?Sized>,
-    std::sync::Weak<T: ?Sized>,
     std::thread::Builder,
     std::thread::JoinHandle<T>,
     std::thread::LocalKey<T>,
     std::thread::Thread,
     std::thread::ThreadId,
-    std::time::Duration,
     std::time::Instant,
     std::time::SystemTime,
-    std::vec::Vec<T>,
 );
 
 macro_rules! impl_type_hash_tuple {
     (($($T: ident,)*)) => {
+        impl<M: ?Sized, $($T: TypeEncoding<M>),*> TypeEncoding<M> for ($($T,)*) {
+            const ENCODING: &'static [Piece] = &[
+                Piece::Tag(Tag::Tuple),
+                Piece::Len(0 $(+ impl_type_hash!(@one $T))*),
+                $(Piece::Type($T::ENCODING),)*
+            ];
+        }
+
         impl <$($T: $crate::TypeHash),*> TypeHash for ($($T,)*) {
-            fn write_hash(hasher: &mut impl std::hash::Hasher) {
-                hasher.write(b"()");
-                $(
-                    $T::write_hash(hasher);
-                )*
+            #[allow(unused_variables)]
+            fn shape_within(ancestors: &Ancestors<'_>) -> Shape {
+                Shape::Tuple(vec![$($T::shape_within(ancestors)),*])
             }
         }
     };
@@ -168,83 +472,194 @@ impl_type_hash_tuple!((A, B, C, D, E, F, G, H, I, J,));
 impl_type_hash_tuple!((A, B, C, D, E, F, G, H, I, J, K,));
 impl_type_hash_tuple!((A, B, C, D, E, F, G, H, I, J, K, L,));
 
-macro_rules! impl_type_hash_array {
-    ([$T: ident; $n: literal]) => {
-        impl<$T: $crate::TypeHash> TypeHash for [$T; $n] {
-            fn write_hash(hasher: &mut impl std::hash::Hasher) {
-                hasher.write(b"[;]");
-                hasher.write_usize($n);
-                $T::write_hash(hasher);
+macro_rules! impl_type_hash_fn {
+    ($($A: ident),*) => {
+        impl_type_hash_fn!(@abi Function, false, "Rust", [fn], $($A),*);
+        impl_type_hash_fn!(@abi UnsafeFunction, true, "Rust", [unsafe fn], $($A),*);
+        impl_type_hash_fn!(@abi Function, false, "C", [extern "C" fn], $($A),*);
+        impl_type_hash_fn!(@abi UnsafeFunction, true, "C", [unsafe extern "C" fn], $($A),*);
+        impl_type_hash_fn!(@abi Function, false, "system", [extern "system" fn], $($A),*);
+        impl_type_hash_fn!(@abi UnsafeFunction, true, "system", [unsafe extern "system" fn], $($A),*);
+    };
+    (@abi $tag: ident, $is_unsafe: literal, $abi: literal, [$($fn: tt)*], $($A: ident),*) => {
+        impl<M: ?Sized, R: TypeEncoding<M> $(, $A: TypeEncoding<M>)*> TypeEncoding<M> for $($fn)*($($A),*) -> R {
+            const ENCODING: &'static [Piece] = &[
+                Piece::Tag(Tag::$tag),
+                Piece::Str($abi),
+                Piece::Len(0 $(+ impl_type_hash!(@one $A))*),
+                $(Piece::Type($A::ENCODING),)*
+                Piece::Type(R::ENCODING),
+            ];
+        }
+
+        impl<R: TypeHash $(, $A: TypeHash)*> TypeHash for $($fn)*($($A),*) -> R {
+            fn shape_within(ancestors: &Ancestors<'_>) -> Shape {
+                Shape::Function {
+                    abi: $abi.to_owned(),
+                    is_unsafe: $is_unsafe,
+                    args: vec![$($A::shape_within(ancestors)),*],
+                    output: Box::new(R::shape_within(ancestors)),
+                }
+            }
+        }
+    };
+}
+
+impl_type_hash_fn!();
+impl_type_hash_fn!(A);
+impl_type_hash_fn!(A, B);
+impl_type_hash_fn!(A, B, C);
+impl_type_hash_fn!(A, B, C, D);
+impl_type_hash_fn!(A, B, C, D, E);
+impl_type_hash_fn!(A, B, C, D, E, F);
+impl_type_hash_fn!(A, B, C, D, E, F, G);
+impl_type_hash_fn!(A, B, C, D, E, F, G, H);
+impl_type_hash_fn!(A, B, C, D, E, F, G, H, I);
+impl_type_hash_fn!(A, B, C, D, E, F, G, H, I, J);
+impl_type_hash_fn!(A, B, C, D, E, F, G, H, I, J, K);
+impl_type_hash_fn!(A, B, C, D, E, F, G, H, I, J, K, L);
+
+macro_rules! impl_type_hash_dyn_fn {
+    ($($A: ident),*) => {
+        impl_type_hash_dyn_fn!(@trait Fn, call, "&self", $($A),*);
+        impl_type_hash_dyn_fn!(@trait FnMut, call_mut, "&mut self", $($A),*);
+        impl_type_hash_dyn_fn!(@trait FnOnce, call_once, "self", $($A),*);
+    };
+    (@trait $Trait: ident, $method: ident, $receiver: literal, $($A: ident),*) => {
+        impl_type_hash_dyn_fn!(@auto $Trait, $method, $receiver, [], [$($A),*]);
+        impl_type_hash_dyn_fn!(@auto $Trait, $method, $receiver, [Send], [$($A),*]);
+        impl_type_hash_dyn_fn!(@auto $Trait, $method, $receiver, [Sync], [$($A),*]);
+        impl_type_hash_dyn_fn!(@auto $Trait, $method, $receiver, [Send, Sync], [$($A),*]);
+    };
+    (@auto $Trait: ident, $method: ident, $receiver: literal, [$($Auto: ident),*], [$($A: ident),*]) => {
+        impl<'a, M: ?Sized, R: TypeEncoding<M> $(, $A: TypeEncoding<M>)*> TypeEncoding<M>
+            for dyn $Trait($($A),*) -> R $(+ $Auto)* + 'a
+        {
+            const ENCODING: &'static [Piece] = &[
+                Piece::Tag(Tag::Trait),
+                Piece::Str(concat!("std::ops::", stringify!($Trait))),
+                Piece::Len(0),
+                Piece::Len(1),
+                Piece::Tag(Tag::Method),
+                Piece::Str(stringify!($method)),
+                Piece::Str($receiver),
+                Piece::Const(ConstArg::Bool(false)),
+                Piece::Len(0 $(+ impl_type_hash!(@one $A))*),
+                $(Piece::Type($A::ENCODING),)*
+                Piece::Type(R::ENCODING),
+                Piece::Len(0 $(+ impl_type_hash!(@one $Auto))*),
+                $(Piece::Str(stringify!($Auto)),)*
+            ];
+        }
+
+        impl<'a, R: TypeHash $(, $A: TypeHash)*> TypeHash for dyn $Trait($($A),*) -> R $(+ $Auto)* + 'a {
+            fn shape_within(ancestors: &Ancestors<'_>) -> Shape {
+                Shape::Trait {
+                    name: concat!("std::ops::", stringify!($Trait)).to_owned(),
+                    supertraits: Vec::new(),
+                    methods: vec![Method {
+                        name: stringify!($method).to_owned(),
+                        receiver: Some($receiver.to_owned()),
+                        is_unsafe: false,
+                        args: vec![$($A::shape_within(ancestors)),*],
+                        output: R::shape_within(ancestors),
+                    }],
+                    auto_traits: vec![$(stringify!($Auto).to_owned()),*],
+                }
             }
         }
     };
 }
 
-impl_type_hash_array!([T; 0]);
-impl_type_hash_array!([T; 1]);
-impl_type_hash_array!([T; 2]);
-impl_type_hash_array!([T; 3]);
-impl_type_hash_array!([T; 4]);
-impl_type_hash_array!([T; 5]);
-impl_type_hash_array!([T; 6]);
-impl_type_hash_array!([T; 7]);
-impl_type_hash_array!([T; 8]);
-impl_type_hash_array!([T; 9]);
-impl_type_hash_array!([T; 10]);
-impl_type_hash_array!([T; 11]);
-impl_type_hash_array!([T; 12]);
-impl_type_hash_array!([T; 13]);
-impl_type_hash_array!([T; 14]);
-impl_type_hash_array!([T; 15]);
-impl_type_hash_array!([T; 16]);
-impl_type_hash_array!([T; 17]);
-impl_type_hash_array!([T; 18]);
-impl_type_hash_array!([T; 19]);
-impl_type_hash_array!([T; 20]);
-impl_type_hash_array!([T; 21]);
-impl_type_hash_array!([T; 22]);
-impl_type_hash_array!([T; 23]);
-impl_type_hash_array!([T; 24]);
-impl_type_hash_array!([T; 25]);
-impl_type_hash_array!([T; 26]);
-impl_type_hash_array!([T; 27]);
-impl_type_hash_array!([T; 28]);
-impl_type_hash_array!([T; 29]);
-impl_type_hash_array!([T; 30]);
-impl_type_hash_array!([T; 31]);
-impl_type_hash_array!([T; 32]);
+impl_type_hash_dyn_fn!();
+impl_type_hash_dyn_fn!(A);
+impl_type_hash_dyn_fn!(A, B);
+impl_type_hash_dyn_fn!(A, B, C);
+impl_type_hash_dyn_fn!(A, B, C, D);
+impl_type_hash_dyn_fn!(A, B, C, D, E);
+impl_type_hash_dyn_fn!(A, B, C, D, E, F);
+impl_type_hash_dyn_fn!(A, B, C, D, E, F, G);
+impl_type_hash_dyn_fn!(A, B, C, D, E, F, G, H);
+impl_type_hash_dyn_fn!(A, B, C, D, E, F, G, H, I);
+impl_type_hash_dyn_fn!(A, B, C, D, E, F, G, H, I, J);
+impl_type_hash_dyn_fn!(A, B, C, D, E, F, G, H, I, J, K);
+impl_type_hash_dyn_fn!(A, B, C, D, E, F, G, H, I, J, K, L);
+
+impl<M: ?Sized, T: TypeEncoding<M>, const N: usize> TypeEncoding<M> for [T; N] {
+    const ENCODING: &'static [Piece] = &[
+        Piece::Tag(Tag::Array),
+        Piece::Len(N),
+        Piece::Type(T::ENCODING),
+    ];
+}
+
+impl<T: TypeHash, const N: usize> TypeHash for [T; N] {
+    fn shape_within(ancestors: &Ancestors<'_>) -> Shape {
+        Shape::Array {
+            len: N,
+            element: Box::new(T::shape_within(ancestors)),
+        }
+    }
+}
+
+impl<M: ?Sized, T: TypeEncoding<M> + ?Sized> TypeEncoding<M> for *const T {
+    const ENCODING: &'static [Piece] = &[Piece::Tag(Tag::ConstPointer), Piece::Type(T::ENCODING)];
+}
 
 impl<T: TypeHash + ?Sized> TypeHash for *const T {
-    fn write_hash(hasher: &mut impl Hasher) {
-        hasher.write(b"*const");
-        T::write_hash(hasher);
+    fn shape_within(ancestors: &Ancestors<'_>) -> Shape {
+        Shape::Pointer {
+            mutable: false,
+            target: Box::new(T::shape_within(ancestors)),
+        }
     }
 }
 
+impl<M: ?Sized, T: TypeEncoding<M> + ?Sized> TypeEncoding<M> for *mut T {
+    const ENCODING: &'static [Piece] = &[Piece::Tag(Tag::MutPointer), Piece::Type(T::ENCODING)];
+}
+
 impl<T: TypeHash + ?Sized> TypeHash for *mut T {
-    fn write_hash(hasher: &mut impl Hasher) {
-        hasher.write(b"*mut");
-        T::write_hash(hasher);
+    fn shape_within(ancestors: &Ancestors<'_>) -> Shape {
+        Shape::Pointer {
+            mutable: true,
+            target: Box::new(T::shape_within(ancestors)),
+        }
     }
 }
 
+impl<M: ?Sized, T: TypeEncoding<M>> TypeEncoding<M> for [T] {
+    const ENCODING: &'static [Piece] = &[Piece::Tag(Tag::Slice), Piece::Type(T::ENCODING)];
+}
+
 impl<T: TypeHash> TypeHash for [T] {
-    fn write_hash(hasher: &mut impl Hasher) {
-        hasher.write(b"[]");
-        T::write_hash(hasher);
+    fn shape_within(ancestors: &Ancestors<'_>) -> Shape {
+        Shape::Slice(Box::new(T::shape_within(ancestors)))
     }
 }
 
-impl<'a, T: TypeHash + ?Sized> TypeHash for &'a T {
-    fn write_hash(hasher: &mut impl Hasher) {
-        hasher.write(b"&");
-        T::write_hash(hasher);
+impl<M: ?Sized, T: TypeEncoding<M> + ?Sized> TypeEncoding<M> for &T {
+    const ENCODING: &'static [Piece] = &[Piece::Tag(Tag::Reference), Piece::Type(T::ENCODING)];
+}
+
+impl<T: TypeHash + ?Sized> TypeHash for &T {
+    fn shape_within(ancestors: &Ancestors<'_>) -> Shape {
+        Shape::Reference {
+            mutable: false,
+            target: Box::new(T::shape_within(ancestors)),
+        }
     }
 }
 
-impl<'a, T: TypeHash + ?Sized> TypeHash for &'a mut T {
-    fn write_hash(hasher: &mut impl Hasher) {
-        hasher.write(b"&mut");
-        T::write_hash(hasher);
+impl<M: ?Sized, T: TypeEncoding<M> + ?Sized> TypeEncoding<M> for &mut T {
+    const ENCODING: &'static [Piece] = &[Piece::Tag(Tag::MutReference), Piece::Type(T::ENCODING)];
+}
+
+impl<T: TypeHash + ?Sized> TypeHash for &mut T {
+    fn shape_within(ancestors: &Ancestors<'_>) -> Shape {
+        Shape::Reference {
+            mutable: true,
+            target: Box::new(T::shape_within(ancestors)),
+        }
     }
 }
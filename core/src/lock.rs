@@ -0,0 +1,258 @@
+//! A lockfile that records the hash and shape of a set of types, so that a change to
+//! any of them is noticed.
+//!
+//! The lockfile is checked in, like `Cargo.lock`, and verified from a test:
+//!
+//! ```ignore
+//! #[test]
+//! fn type_hashes_are_locked() {
+//!     let lockfile = Lockfile::new()
+//!         .with_name::<Message>("messages::Message")
+//!         .with_name::<Reply>("messages::Reply");
+//!     if let Err(e) = lockfile.check("type_hash.lock") {
+//!         panic!("{}", e);
+//!     }
+//! }
+//! ```
+//!
+//! [`Lockfile::check`] fails if a type's hash differs from the locked one, listing what
+//! changed, or if types were added or removed. When the `TYPE_HASH_UPDATE` environment
+//! variable is set, it writes the lockfile instead.
+//!
+//! Types are keyed by the names they are given, and types from the
+//! [`registry`](crate::registry) by their registered names, which are
+//! `module_path!()::Name`. Names from [`std::any::type_name`] are not used, because
+//! they can change between compiler versions, which would make every type look like it
+//! had been removed and added again.
+//!
+//! The lockfile is a [`json`](crate::json) document, with the types keyed by name:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "types": {
+//!     "messages::Point": {
+//!       "hash": 11190051055522503592,
+//!       "shape": { "struct": { "name": "Point", "fields": "unit" } }
+//!     }
+//!   }
+//! }
+//! ```
+use crate::diff::{diff, Change};
+use crate::json;
+use crate::shape::Shape;
+use crate::snapshot::UPDATE_VAR;
+use crate::TypeHash;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// The hashes and shapes of a set of types
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lockfile {
+    version: u32,
+    /// The locked types, by name
+    pub types: BTreeMap<String, Locked>,
+}
+
+impl Default for Lockfile {
+    fn default() -> Lockfile {
+        Lockfile::new()
+    }
+}
+
+/// The hash and shape of a type in a [`Lockfile`]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Locked {
+    pub hash: u64,
+    pub shape: Shape,
+}
+
+impl Locked {
+    pub fn new(shape: Shape) -> Locked {
+        Locked {
+            hash: shape.type_hash(),
+            shape,
+        }
+    }
+}
+
+impl Lockfile {
+    /// A lockfile with no types
+    pub fn new() -> Lockfile {
+        Lockfile {
+            version: json::VERSION,
+            types: BTreeMap::new(),
+        }
+    }
+
+    /// Add the type `T` with the given name, such as `"messages::Message"`
+    pub fn with_name<T: TypeHash + ?Sized>(mut self, name: impl Into<String>) -> Lockfile {
+        self.insert(name, T::shape());
+        self
+    }
+
+    /// Add every type in the [`registry`](crate::registry), named by its registered
+    /// name, which is `module_path!()::Name`.
+    ///
+    /// Only one of the types that share a name is added.
+    /// [`check_collisions`](crate::registry::check_collisions) reports such types.
+    #[cfg(feature = "registry")]
+    pub fn with_registered(mut self) -> Lockfile {
+        for registered in crate::registry::all() {
+            self.insert(registered.name(), registered.shape());
+        }
+        self
+    }
+
+    /// Add a type by its shape, replacing any type with the same name
+    pub fn insert(&mut self, name: impl Into<String>, shape: Shape) {
+        self.types.insert(name.into(), Locked::new(shape));
+    }
+
+    /// Write the lockfile as a JSON document
+    pub fn to_json(&self) -> String {
+        let mut json =
+            serde_json::to_string_pretty(self).expect("a lockfile can always be serialized");
+        json.push('\n');
+        json
+    }
+
+    /// Read a lockfile from a JSON document
+    pub fn from_json(json: &str) -> Result<Lockfile, json::Error> {
+        json::read(json)
+    }
+
+    /// Read a lockfile from a file
+    pub fn read(path: impl AsRef<Path>) -> Result<Lockfile, Error> {
+        let json = fs::read_to_string(path).map_err(Error::Io)?;
+        Lockfile::from_json(&json).map_err(Error::Json)
+    }
+
+    /// Write the lockfile to a file, if its contents have changed
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let json = self.to_json();
+        if fs::read_to_string(&path).ok().as_ref() == Some(&json) {
+            return Ok(());
+        }
+        fs::write(path, json)
+    }
+
+    /// Compare the types with the types in the lockfile at `path`
+    pub fn verify(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let drift = self.drift_from(&Lockfile::read(path)?);
+        if drift.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Drift(drift))
+        }
+    }
+
+    /// Write the lockfile to `path` if the `TYPE_HASH_UPDATE` environment variable is
+    /// set to anything other than `0`, and otherwise [`verify`](Lockfile::verify) it.
+    pub fn check(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        if std::env::var_os(UPDATE_VAR).is_some_and(|value| value != "0") {
+            self.write(path).map_err(Error::Io)
+        } else {
+            self.verify(path)
+        }
+    }
+
+    /// How these types differ from the ones in `locked`
+    pub fn drift_from(&self, locked: &Lockfile) -> Vec<Drift> {
+        let removed = locked
+            .types
+            .keys()
+            .filter(|name| !self.types.contains_key(*name))
+            .map(|name| Drift::Removed(name.clone()));
+        let changed =
+            self.types
+                .iter()
+                .filter_map(|(name, current)| match locked.types.get(name) {
+                    None => Some(Drift::Added(name.clone())),
+                    Some(old) if old.hash == current.hash => None,
+                    Some(old) => Some(Drift::Changed {
+                        name: name.clone(),
+                        old: old.hash,
+                        new: current.hash,
+                        changes: diff(&old.shape, &current.shape),
+                    }),
+                });
+        removed.chain(changed).collect()
+    }
+}
+
+/// A difference between a set of types and a lockfile
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Drift {
+    /// The hash of a type differs from the locked hash
+    Changed {
+        name: String,
+        old: u64,
+        new: u64,
+        changes: Vec<Change>,
+    },
+    /// A type is not in the lockfile
+    Added(String),
+    /// A type in the lockfile is no longer included
+    Removed(String),
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Drift::Changed {
+                name,
+                old,
+                new,
+                changes,
+            } => {
+                write!(f, "{}: hash changed {:#018x} -> {:#018x}", name, old, new)?;
+                for change in changes {
+                    write!(f, "\n  {}", change)?;
+                }
+                Ok(())
+            }
+            Drift::Added(name) => write!(f, "{}: not in the lockfile", name),
+            Drift::Removed(name) => write!(f, "{}: in the lockfile, but no longer included", name),
+        }
+    }
+}
+
+/// An error reading or verifying a lockfile
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Json(json::Error),
+    /// The types differ from the lockfile
+    Drift(Vec<Drift>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "could not access the lockfile: {}", e),
+            Error::Json(e) => write!(f, "could not read the lockfile: {}", e),
+            Error::Drift(drift) => {
+                writeln!(f, "the types differ from the lockfile:")?;
+                for drift in drift {
+                    writeln!(f, "{}", drift)?;
+                }
+                write!(f, "set {}=1 to update the lockfile", UPDATE_VAR)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Json(e) => Some(e),
+            Error::Drift(_) => None,
+        }
+    }
+}
@@ -0,0 +1,251 @@
+//! All of the types in a program that were derived with `#[type_hash(register)]`.
+//!
+//! Registering a type makes it available here without a list of types being written by
+//! hand, for example to lock their hashes or to dispatch messages by their hash:
+//!
+//! ```ignore
+//! #[derive(TypeHash)]
+//! #[type_hash(register)]
+//! pub struct Ping;
+//!
+//! let ping = type_hash::registry::by_hash(Ping::type_hash()).next().unwrap();
+//! assert_eq!(ping.name(), "my_crate::messages::Ping");
+//! ```
+//!
+//! Types are collected when the program is linked, so types in every crate of the
+//! program are included. Only types without generic parameters can be registered.
+use crate::shape::Shape;
+use crate::TypeHash;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+#[doc(hidden)]
+pub use inventory::submit;
+
+/// A registered type
+#[derive(Debug)]
+pub struct Registered {
+    name: &'static str,
+    type_name: fn() -> &'static str,
+    shape: fn() -> Shape,
+    hash: fn() -> u64,
+}
+
+inventory::collect!(Registered);
+
+impl Registered {
+    #[doc(hidden)]
+    pub const fn new<T: TypeHash>(name: &'static str) -> Registered {
+        Registered {
+            name,
+            type_name: core::any::type_name::<T>,
+            shape: T::shape,
+            hash: T::type_hash,
+        }
+    }
+
+    /// The full path of the type, such as `"my_crate::messages::Ping"`. This is the
+    /// module of the type followed by its name in the hash, which is given by
+    /// `#[type_hash(name = "...")]` if it is renamed.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The type as it is named by [`std::any::type_name`], which is only for display
+    pub fn type_name(&self) -> &'static str {
+        (self.type_name)()
+    }
+
+    /// The structure of the type
+    pub fn shape(&self) -> Shape {
+        (self.shape)()
+    }
+
+    /// The 64 bit FNV-1a hash of the type's structure
+    pub fn type_hash(&self) -> u64 {
+        (self.hash)()
+    }
+}
+
+/// Every registered type, in no particular order
+pub fn all() -> impl Iterator<Item = &'static Registered> {
+    inventory::iter::<Registered>.into_iter()
+}
+
+/// The registered type with the full path `name`, or `None` if there is more than one.
+/// See [`duplicate_names`].
+pub fn by_name(name: &str) -> Option<&'static Registered> {
+    match by_name_index().get(name)?.as_slice() {
+        [registered] => Some(registered),
+        _ => None,
+    }
+}
+
+// The registered types, keyed by name, each list ordered by `type_name`
+fn by_name_index() -> &'static HashMap<&'static str, Vec<&'static Registered>> {
+    static INDEX: OnceLock<HashMap<&'static str, Vec<&'static Registered>>> = OnceLock::new();
+    INDEX.get_or_init(|| {
+        let mut index: HashMap<&'static str, Vec<&'static Registered>> = HashMap::new();
+        for registered in all() {
+            index.entry(registered.name).or_default().push(registered);
+        }
+        for types in index.values_mut() {
+            types.sort_by_key(|registered| registered.type_name());
+        }
+        index
+    })
+}
+
+/// The registered types with the hash `hash`, ordered by name. There is normally only
+/// one, unless types with the same structure are registered under different paths. See
+/// [`collisions`].
+///
+/// The hashes of all of the registered types are computed on the first call, so later
+/// calls are cheap enough to dispatch each message by its hash.
+pub fn by_hash(hash: u64) -> impl Iterator<Item = &'static Registered> {
+    by_hash_index().get(&hash).into_iter().flatten().copied()
+}
+
+// The registered types, keyed by hash, each list ordered by name
+fn by_hash_index() -> &'static HashMap<u64, Vec<&'static Registered>> {
+    static INDEX: OnceLock<HashMap<u64, Vec<&'static Registered>>> = OnceLock::new();
+    INDEX.get_or_init(|| {
+        let mut index: HashMap<u64, Vec<&'static Registered>> = HashMap::new();
+        for registered in all() {
+            index
+                .entry(registered.type_hash())
+                .or_default()
+                .push(registered);
+        }
+        for types in index.values_mut() {
+            types.sort_by_key(|registered| registered.name);
+        }
+        index
+    })
+}
+
+/// Registered types that have the same hash
+#[derive(Debug)]
+pub struct Collision {
+    pub hash: u64,
+    /// The types, ordered by name
+    pub types: Vec<&'static Registered>,
+}
+
+impl fmt::Display for Collision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = self
+            .types
+            .iter()
+            .map(|registered| registered.name)
+            .collect();
+        write!(f, "{:#018x} is the hash of {}", self.hash, names.join(", "))?;
+        let shape = self.types[0].shape();
+        if self.types[1..].iter().all(|other| other.shape() == shape) {
+            write!(f, ", which have the same structure")
+        } else {
+            write!(f, ", which have different structures")
+        }
+    }
+}
+
+/// All of the hashes that are shared by more than one registered type, ordered by hash
+pub fn collisions() -> Vec<Collision> {
+    let mut collisions: Vec<_> = by_hash_index()
+        .iter()
+        .filter(|(_, types)| types.len() > 1)
+        .map(|(&hash, types)| Collision {
+            hash,
+            types: types.clone(),
+        })
+        .collect();
+    collisions.sort_by_key(|collision| collision.hash);
+    collisions
+}
+
+/// Registered types that have the same name, because they are in the same module and
+/// are renamed to the same name with `#[type_hash(name = "...")]`
+#[derive(Debug)]
+pub struct DuplicateName {
+    pub name: &'static str,
+    /// The types, ordered by [`Registered::type_name`]
+    pub types: Vec<&'static Registered>,
+}
+
+impl fmt::Display for DuplicateName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let type_names: Vec<_> = self
+            .types
+            .iter()
+            .map(|registered| registered.type_name())
+            .collect();
+        write!(f, "{} is the name of {}", self.name, type_names.join(", "))
+    }
+}
+
+/// All of the names that are shared by more than one registered type, ordered by name
+pub fn duplicate_names() -> Vec<DuplicateName> {
+    let mut duplicates: Vec<_> = by_name_index()
+        .iter()
+        .filter(|(_, types)| types.len() > 1)
+        .map(|(&name, types)| DuplicateName {
+            name,
+            types: types.clone(),
+        })
+        .collect();
+    duplicates.sort_by_key(|duplicate| duplicate.name);
+    duplicates
+}
+
+/// Check that no two registered types have the same hash or the same name, for example
+/// when a program that identifies messages by their hash starts.
+pub fn check_collisions() -> Result<(), Collisions> {
+    let collisions = Collisions {
+        hashes: collisions(),
+        names: duplicate_names(),
+    };
+    if collisions.hashes.is_empty() && collisions.names.is_empty() {
+        Ok(())
+    } else {
+        Err(collisions)
+    }
+}
+
+/// Panic if any two registered types have the same hash or the same name. This is
+/// intended for tests.
+pub fn assert_no_collisions() {
+    if let Err(e) = check_collisions() {
+        panic!("{}", e);
+    }
+}
+
+/// The error when registered types have the same hash or the same name
+#[derive(Debug)]
+pub struct Collisions {
+    pub hashes: Vec<Collision>,
+    pub names: Vec<DuplicateName>,
+}
+
+impl fmt::Display for Collisions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.hashes.is_empty() {
+            write!(f, "registered types have the same hash:")?;
+            for collision in &self.hashes {
+                write!(f, "\n{}", collision)?;
+            }
+        }
+        if !self.names.is_empty() {
+            if !self.hashes.is_empty() {
+                writeln!(f)?;
+            }
+            write!(f, "registered types have the same name:")?;
+            for duplicate in &self.names {
+                write!(f, "\n{}", duplicate)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Collisions {}
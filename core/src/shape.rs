@@ -0,0 +1,599 @@
+//! A description of a type's structure.
+//!
+//! The [`Shape`] of a type is what its hash is computed from: [`Shape::write_hash`]
+//! writes the encoding described in [`encoding`](crate::encoding), and
+//! [`TypeHash::type_hash`](crate::TypeHash::type_hash) is the hash of those bytes. This
+//! means that two types with the same shape always have the same hash.
+use crate::encoding::{write_const_arg, write_discriminant, write_len, write_str, write_tag, Tag};
+use crate::hasher;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::Hasher;
+
+/// The structure of a type
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Shape {
+    /// A type that is built in to the language, such as `u32`, `char` or `str`
+    Primitive(String),
+    /// A struct, tuple struct or unit struct
+    Struct { name: String, fields: Fields },
+    /// A union and its fields
+    Union { name: String, fields: Vec<Field> },
+    /// An enum and its variants, in declaration order
+    Enum {
+        name: String,
+        variants: Vec<Variant>,
+    },
+    /// A tuple
+    Tuple(Vec<Shape>),
+    /// A collection of elements from the standard library, such as `Vec<T>`
+    Sequence {
+        name: String,
+        element: Box<Shape>,
+        /// The hasher of a `HashSet`, only from a field marked `#[type_hash(hasher)]`
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, skip_serializing_if = "Option::is_none")
+        )]
+        hasher: Option<Box<Shape>>,
+    },
+    /// A map from the standard library, such as `HashMap<K, V>`
+    Map {
+        name: String,
+        key: Box<Shape>,
+        value: Box<Shape>,
+        /// The hasher of a `HashMap`, only from a field marked `#[type_hash(hasher)]`
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, skip_serializing_if = "Option::is_none")
+        )]
+        hasher: Option<Box<Shape>>,
+    },
+    /// An array with a fixed length
+    Array { len: usize, element: Box<Shape> },
+    /// A slice
+    Slice(Box<Shape>),
+    /// A shared or mutable reference
+    Reference { mutable: bool, target: Box<Shape> },
+    /// A const or mutable raw pointer
+    Pointer { mutable: bool, target: Box<Shape> },
+    /// A function pointer, such as `unsafe extern "C" fn(*const u8) -> i32`
+    Function {
+        abi: String,
+        is_unsafe: bool,
+        args: Vec<Shape>,
+        output: Box<Shape>,
+    },
+    /// A trait object, such as `dyn Handler + Send`, described by the methods of the
+    /// trait
+    Trait {
+        name: String,
+        /// The supertraits of the trait. Those that are also `#[trait_hash]` traits are
+        /// described by their trait objects, and those from the standard library, such
+        /// as `Send` or `Debug`, and lifetimes are [`Shape::Foreign`]s with their names.
+        supertraits: Vec<Shape>,
+        /// The methods that can be called on a trait object, in declaration order
+        methods: Vec<Method>,
+        /// The auto traits of the trait object, such as `Send` and `Sync`
+        auto_traits: Vec<String>,
+    },
+    /// A type that does not implement `TypeHash`, and is only known by how it was
+    /// written in the source, from a field marked `#[type_hash(foreign_type)]`, or a
+    /// supertrait from the standard library
+    Foreign(String),
+    /// A type that is described by the bytes that a function wrote, from a field marked
+    /// `#[type_hash(with = "...")]`. The name is how the type was written in the source,
+    /// and is only used to display the type, and is not part of its hash.
+    Custom { name: String, bytes: Vec<u8> },
+    /// A type from the standard library whose structure is not described, such as
+    /// `Option<T>` or `Duration`, with its type arguments
+    Opaque { name: String, args: Vec<Shape> },
+    /// A type with const parameters, and the values that they were given
+    ConstArgs {
+        args: Vec<ConstArg>,
+        shape: Box<Shape>,
+    },
+    /// A reference to a type that contains this one. The depth counts how many types
+    /// up the tree it is, where `0` is the closest named type. The name is only used to
+    /// display the type, and is not part of its hash.
+    BackRef { depth: usize, name: String },
+}
+
+/// The fields of a struct or variant
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Fields {
+    Unit,
+    Named(Vec<Field>),
+    Unnamed(Vec<Shape>),
+}
+
+/// A named field of a struct or variant
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub struct Field {
+    pub name: String,
+    pub shape: Shape,
+}
+
+/// A variant of an enum
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub struct Variant {
+    pub name: String,
+    pub discriminant: Option<i128>,
+    pub fields: Fields,
+}
+
+/// A method of a trait
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub struct Method {
+    pub name: String,
+    /// How `self` is taken, such as `&self` or `self: Box<Self>`, or `None` if the
+    /// method has no receiver
+    pub receiver: Option<String>,
+    pub is_unsafe: bool,
+    pub args: Vec<Shape>,
+    pub output: Shape,
+}
+
+/// The value of a const generic argument
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ConstArg {
+    /// An integer of any type
+    Int(i128),
+    Bool(bool),
+    Char(char),
+}
+
+impl Shape {
+    /// The 64 bit FNV-1a hash of this shape
+    pub fn type_hash(&self) -> u64 {
+        self.type_hash_with::<fnv::FnvHasher>()
+    }
+
+    /// The hash of this shape, computed with the hasher `H`
+    pub fn type_hash_with<H: Hasher + Default>(&self) -> u64 {
+        let mut hasher = H::default();
+        self.write_hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// The 128 bit FNV-1a hash of this shape
+    pub fn type_hash_128(&self) -> u128 {
+        let mut hasher = hasher::Fnv128Hasher::default();
+        self.write_hash(&mut hasher);
+        hasher.finish_128()
+    }
+
+    /// The SHA-256 digest of this shape
+    #[cfg(feature = "sha2")]
+    pub fn type_hash_sha256(&self) -> [u8; 32] {
+        let mut hasher = hasher::DigestHasher::<sha2::Sha256>::default();
+        self.write_hash(&mut hasher);
+        hasher.finalize().into()
+    }
+
+    /// The name of a struct, union or enum
+    pub(crate) fn type_name(&self) -> Option<&str> {
+        match self {
+            Shape::Struct { name, .. } | Shape::Union { name, .. } | Shape::Enum { name, .. } => {
+                Some(name)
+            }
+            Shape::Trait { name, .. } => Some(name),
+            Shape::ConstArgs { shape, .. } => shape.type_name(),
+            _ => None,
+        }
+    }
+
+    /// Write the encoding of this shape to the hasher
+    pub fn write_hash(&self, hasher: &mut impl Hasher) {
+        match self {
+            Shape::Primitive(name) => {
+                write_tag(hasher, Tag::Primitive);
+                write_str(hasher, name);
+            }
+            Shape::Struct { name, fields } => fields.write_hash(hasher, Some(name)),
+            Shape::Union { name, fields } => {
+                write_tag(hasher, Tag::Union);
+                write_str(hasher, name);
+                write_len(hasher, fields.len());
+                for field in fields {
+                    field.write_hash(hasher);
+                }
+            }
+            Shape::Enum { name, variants } => {
+                write_tag(hasher, Tag::Enum);
+                write_str(hasher, name);
+                write_len(hasher, variants.len());
+                for (index, variant) in variants.iter().enumerate() {
+                    write_tag(hasher, Tag::Variant);
+                    write_len(hasher, index);
+                    write_str(hasher, &variant.name);
+                    if let Some(discriminant) = variant.discriminant {
+                        write_discriminant(hasher, discriminant);
+                    }
+                    variant.fields.write_hash(hasher, None);
+                }
+            }
+            Shape::Tuple(elements) => {
+                write_tag(hasher, Tag::Tuple);
+                write_len(hasher, elements.len());
+                for element in elements {
+                    element.write_hash(hasher);
+                }
+            }
+            Shape::Sequence {
+                name,
+                element,
+                hasher: build_hasher,
+            } => {
+                write_tag(hasher, Tag::Sequence);
+                write_str(hasher, name);
+                element.write_hash(hasher);
+                write_build_hasher(hasher, build_hasher.as_deref());
+            }
+            Shape::Map {
+                name,
+                key,
+                value,
+                hasher: build_hasher,
+            } => {
+                write_tag(hasher, Tag::Map);
+                write_str(hasher, name);
+                key.write_hash(hasher);
+                value.write_hash(hasher);
+                write_build_hasher(hasher, build_hasher.as_deref());
+            }
+            Shape::Array { len, element } => {
+                write_tag(hasher, Tag::Array);
+                write_len(hasher, *len);
+                element.write_hash(hasher);
+            }
+            Shape::Slice(element) => {
+                write_tag(hasher, Tag::Slice);
+                element.write_hash(hasher);
+            }
+            Shape::Reference { mutable, target } => {
+                write_tag(
+                    hasher,
+                    if *mutable {
+                        Tag::MutReference
+                    } else {
+                        Tag::Reference
+                    },
+                );
+                target.write_hash(hasher);
+            }
+            Shape::Pointer { mutable, target } => {
+                write_tag(
+                    hasher,
+                    if *mutable {
+                        Tag::MutPointer
+                    } else {
+                        Tag::ConstPointer
+                    },
+                );
+                target.write_hash(hasher);
+            }
+            Shape::Function {
+                abi,
+                is_unsafe,
+                args,
+                output,
+            } => {
+                write_tag(
+                    hasher,
+                    if *is_unsafe {
+                        Tag::UnsafeFunction
+                    } else {
+                        Tag::Function
+                    },
+                );
+                write_str(hasher, abi);
+                write_len(hasher, args.len());
+                for arg in args {
+                    arg.write_hash(hasher);
+                }
+                output.write_hash(hasher);
+            }
+            Shape::Trait {
+                name,
+                supertraits,
+                methods,
+                auto_traits,
+            } => {
+                write_tag(hasher, Tag::Trait);
+                write_str(hasher, name);
+                write_len(hasher, supertraits.len());
+                for supertrait in supertraits {
+                    supertrait.write_hash(hasher);
+                }
+                write_len(hasher, methods.len());
+                for method in methods {
+                    method.write_hash(hasher);
+                }
+                write_len(hasher, auto_traits.len());
+                for auto_trait in auto_traits {
+                    write_str(hasher, auto_trait);
+                }
+            }
+            Shape::Foreign(name) => {
+                write_tag(hasher, Tag::Foreign);
+                write_str(hasher, name);
+            }
+            Shape::Custom { bytes, .. } => {
+                write_tag(hasher, Tag::Custom);
+                write_len(hasher, bytes.len());
+                hasher.write(bytes);
+            }
+            Shape::Opaque { name, args } => {
+                write_tag(hasher, Tag::Opaque);
+                write_str(hasher, name);
+                write_len(hasher, args.len());
+                for arg in args {
+                    arg.write_hash(hasher);
+                }
+            }
+            Shape::ConstArgs { args, shape } => {
+                write_tag(hasher, Tag::ConstArgs);
+                write_len(hasher, args.len());
+                for arg in args {
+                    write_const_arg(hasher, *arg);
+                }
+                shape.write_hash(hasher);
+            }
+            Shape::BackRef { depth, .. } => {
+                write_tag(hasher, Tag::BackRef);
+                write_len(hasher, *depth);
+            }
+        }
+    }
+}
+
+/// Write the hasher of a collection, if it is part of the hash
+fn write_build_hasher(hasher: &mut impl Hasher, build_hasher: Option<&Shape>) {
+    if let Some(build_hasher) = build_hasher {
+        write_tag(hasher, Tag::Hasher);
+        build_hasher.write_hash(hasher);
+    }
+}
+
+impl Fields {
+    /// Write the kind of struct, its name if it has one, and then its fields
+    fn write_hash(&self, hasher: &mut impl Hasher, name: Option<&str>) {
+        write_tag(
+            hasher,
+            match self {
+                Fields::Unit => Tag::UnitStruct,
+                Fields::Named(_) => Tag::Struct,
+                Fields::Unnamed(_) => Tag::TupleStruct,
+            },
+        );
+        if let Some(name) = name {
+            write_str(hasher, name);
+        }
+        match self {
+            Fields::Unit => {}
+            Fields::Named(fields) => {
+                write_len(hasher, fields.len());
+                for field in fields {
+                    field.write_hash(hasher);
+                }
+            }
+            Fields::Unnamed(fields) => {
+                write_len(hasher, fields.len());
+                for shape in fields {
+                    write_tag(hasher, Tag::Field);
+                    shape.write_hash(hasher);
+                }
+            }
+        }
+    }
+}
+
+impl Field {
+    fn write_hash(&self, hasher: &mut impl Hasher) {
+        write_tag(hasher, Tag::Field);
+        write_str(hasher, &self.name);
+        self.shape.write_hash(hasher);
+    }
+}
+
+impl Method {
+    fn write_hash(&self, hasher: &mut impl Hasher) {
+        write_tag(hasher, Tag::Method);
+        write_str(hasher, &self.name);
+        write_str(hasher, self.receiver.as_deref().unwrap_or(""));
+        write_const_arg(hasher, ConstArg::Bool(self.is_unsafe));
+        write_len(hasher, self.args.len());
+        for arg in &self.args {
+            arg.write_hash(hasher);
+        }
+        self.output.write_hash(hasher);
+    }
+}
+
+/// Displays the shape as a type, such as `Vec<(u8, Foo)>`
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Shape::Primitive(name) | Shape::Foreign(name) => f.write_str(name),
+            Shape::Struct { name, .. } | Shape::Union { name, .. } | Shape::Enum { name, .. } => {
+                f.write_str(name)
+            }
+            Shape::Custom { name, .. } | Shape::BackRef { name, .. } => f.write_str(name),
+            Shape::Tuple(elements) => {
+                f.write_str("(")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                if elements.len() == 1 {
+                    f.write_str(",")?;
+                }
+                f.write_str(")")
+            }
+            Shape::Sequence {
+                name,
+                element,
+                hasher: None,
+            } => write!(f, "{}<{}>", short_path(name), element),
+            Shape::Sequence {
+                name,
+                element,
+                hasher: Some(hasher),
+            } => write!(f, "{}<{}, {}>", short_path(name), element, hasher),
+            Shape::Map {
+                name,
+                key,
+                value,
+                hasher: None,
+            } => write!(f, "{}<{}, {}>", short_path(name), key, value),
+            Shape::Map {
+                name,
+                key,
+                value,
+                hasher: Some(hasher),
+            } => {
+                write!(f, "{}<{}, {}, {}>", short_path(name), key, value, hasher)
+            }
+            Shape::Array { len, element } => write!(f, "[{}; {}]", element, len),
+            Shape::Slice(element) => write!(f, "[{}]", element),
+            Shape::Reference { mutable, target } => {
+                write!(f, "&{}{}", if *mutable { "mut " } else { "" }, target)
+            }
+            Shape::Pointer { mutable, target } => {
+                write!(f, "*{} {}", if *mutable { "mut" } else { "const" }, target)
+            }
+            Shape::Function {
+                abi,
+                is_unsafe,
+                args,
+                output,
+            } => {
+                if *is_unsafe {
+                    f.write_str("unsafe ")?;
+                }
+                if abi != "Rust" {
+                    write!(f, "extern {:?} ", abi)?;
+                }
+                f.write_str("fn(")?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                f.write_str(")")?;
+                match &**output {
+                    Shape::Tuple(elements) if elements.is_empty() => Ok(()),
+                    output => write!(f, " -> {}", output),
+                }
+            }
+            Shape::Trait {
+                name, auto_traits, ..
+            } => {
+                write!(f, "dyn {}", short_path(name))?;
+                for auto_trait in auto_traits {
+                    write!(f, " + {}", auto_trait)?;
+                }
+                Ok(())
+            }
+            Shape::ConstArgs { args, shape } => {
+                write!(f, "{}<", shape)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                f.write_str(">")
+            }
+            Shape::Opaque { name, args } => {
+                f.write_str(short_path(name))?;
+                if !args.is_empty() {
+                    f.write_str("<")?;
+                    for (i, arg) in args.iter().enumerate() {
+                        if i > 0 {
+                            f.write_str(", ")?;
+                        }
+                        write!(f, "{}", arg)?;
+                    }
+                    f.write_str(">")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Displays the method's signature, such as `fn handle(&mut self, u8) -> bool`
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_unsafe {
+            f.write_str("unsafe ")?;
+        }
+        write!(f, "fn {}(", self.name)?;
+        let receiver = self
+            .receiver
+            .iter()
+            .map(|receiver| receiver as &dyn fmt::Display);
+        let args = self.args.iter().map(|arg| arg as &dyn fmt::Display);
+        for (i, arg) in receiver.chain(args).enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", arg)?;
+        }
+        f.write_str(")")?;
+        match &self.output {
+            Shape::Tuple(elements) if elements.is_empty() => Ok(()),
+            output => write!(f, " -> {}", output),
+        }
+    }
+}
+
+impl fmt::Display for ConstArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstArg::Int(value) => write!(f, "{}", value),
+            ConstArg::Bool(value) => write!(f, "{}", value),
+            ConstArg::Char(value) => write!(f, "{:?}", value),
+        }
+    }
+}
+
+/// The last segment of a path, such as `Vec` for `std::vec::Vec`
+fn short_path(path: &str) -> &str {
+    path.rsplit("::").next().unwrap_or(path)
+}
+
+/// Remove the paths from a type name, so that `std::vec::Vec<my_crate::Foo>` becomes
+/// `Vec<Foo>`
+pub(crate) fn short_type_name(name: &str) -> String {
+    let mut short = String::with_capacity(name.len());
+    for (i, segment) in name.split("::").enumerate() {
+        if i > 0 {
+            let path_start = short
+                .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .map_or(0, |i| i + 1);
+            short.truncate(path_start);
+        }
+        short.push_str(segment);
+    }
+    short
+}
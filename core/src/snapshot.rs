@@ -0,0 +1,382 @@
+//! Support for [`assert_type_hash!`](crate::assert_type_hash), which checks a type's
+//! hash against a value written in the source, and can update it.
+//!
+//! When the `TYPE_HASH_UPDATE` environment variable is set to anything other than `0`,
+//! an assertion that fails instead replaces the expected hash in the source file with
+//! the new one, and the test passes:
+//!
+//! ```text
+//! TYPE_HASH_UPDATE=1 cargo test
+//! ```
+//!
+//! Updating an assertion also records the shape of the type in a `snapshots` directory
+//! next to the source file. When the assertion fails later, it lists what changed since
+//! then. With the `serde` feature, the shape is recorded as a [`json`](crate::json)
+//! document and the changes are found with [`diff`](crate::diff::diff). Without it, the structure of the
+//! type is recorded as text, one line for each field, and the changes are the lines
+//! that were removed or added.
+use crate::diff::{self, Segment};
+use crate::shape::{Fields, Shape};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// The environment variable that turns on updating expected hashes
+pub const UPDATE_VAR: &str = "TYPE_HASH_UPDATE";
+
+// Several assertions in the same file may be updated by tests running in parallel
+static SOURCE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Assert that the hash of a type is `expected`.
+///
+/// ```ignore
+/// type_hash::assert_type_hash!(Message, 0x8a0eeb94bb00c95e);
+/// ```
+///
+/// The expected hash must be an integer literal, so that it can be updated by setting
+/// `TYPE_HASH_UPDATE`. See the [`snapshot`](crate::snapshot) module.
+#[macro_export]
+macro_rules! assert_type_hash {
+    ($ty: ty, $expected: literal $(,)?) => {
+        $crate::snapshot::assert_type_hash(
+            &<$ty as $crate::TypeHash>::shape(),
+            &$crate::snapshot::Assertion {
+                type_name: stringify!($ty),
+                expected: $expected,
+                literal: stringify!($expected),
+                manifest_dir: env!("CARGO_MANIFEST_DIR"),
+                file: file!(),
+                line: line!(),
+                column: column!(),
+            },
+        )
+    };
+}
+
+/// An [`assert_type_hash!`](crate::assert_type_hash), and where it is in the source
+#[derive(Clone, Debug)]
+pub struct Assertion {
+    /// The type, as it is written in the assertion
+    pub type_name: &'static str,
+    pub expected: u64,
+    /// The expected hash, as it is written in the assertion
+    pub literal: &'static str,
+    /// The directory of the crate that contains the assertion
+    pub manifest_dir: &'static str,
+    /// The source file, as given by `file!()`
+    pub file: &'static str,
+    pub line: u32,
+    pub column: u32,
+}
+
+/// Check the hash of `shape`, panicking if it is not the expected one, unless the
+/// assertion can be updated.
+pub fn assert_type_hash(shape: &Shape, assertion: &Assertion) {
+    let update = std::env::var_os(UPDATE_VAR).is_some_and(|value| value != "0");
+    match assertion.check(shape) {
+        Ok(()) if update => record(assertion, shape),
+        Ok(()) => {}
+        Err(_) if update => {
+            record(assertion, shape);
+            eprintln!(
+                "updated the hash of `{}` at {}:{}",
+                assertion.type_name, assertion.file, assertion.line
+            );
+        }
+        Err(mismatch) => panic!("{}", mismatch),
+    }
+}
+
+fn record(assertion: &Assertion, shape: &Shape) {
+    if let Err(e) = assertion.update(shape) {
+        panic!(
+            "could not update the hash of `{}` at {}:{}: {}",
+            assertion.type_name, assertion.file, assertion.line, e
+        );
+    }
+}
+
+impl Assertion {
+    /// The source file that contains the assertion
+    pub fn source_path(&self) -> PathBuf {
+        let file = Path::new(self.file);
+        let manifest_dir = Path::new(self.manifest_dir);
+        // `file!()` is relative to the workspace, which may be above the crate
+        manifest_dir
+            .ancestors()
+            .map(|dir| dir.join(file))
+            .find(|path| path.is_file())
+            .unwrap_or_else(|| manifest_dir.join(file))
+    }
+
+    /// Where the shape of the type is recorded, which is a `.json` file with the `serde`
+    /// feature and a `.txt` file without it
+    pub fn shape_path(&self) -> PathBuf {
+        let source = self.source_path();
+        let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+        let type_name: String = self
+            .type_name
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        source
+            .with_file_name("snapshots")
+            .join(format!("{}__{}.{}", stem, type_name, SNAPSHOT_EXTENSION))
+    }
+
+    /// Compare the hash of `shape` with the expected hash
+    pub fn check(&self, shape: &Shape) -> Result<(), Mismatch> {
+        let found = shape.type_hash();
+        if found == self.expected {
+            return Ok(());
+        }
+        Err(Mismatch {
+            type_name: self.type_name,
+            expected: self.expected,
+            found,
+            structure: structure(shape),
+            changes: self.recorded_changes(shape),
+        })
+    }
+
+    /// Replace the expected hash in the source with the hash of `shape`, and record the
+    /// shape.
+    pub fn update(&self, shape: &Shape) -> io::Result<()> {
+        let found = shape.type_hash();
+        if found != self.expected {
+            self.update_source(found)?;
+        }
+        self.record_shape(shape)
+    }
+
+    fn update_source(&self, hash: u64) -> io::Result<()> {
+        let _lock = SOURCE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let path = self.source_path();
+        let source = fs::read_to_string(&path)?;
+        let start = self.offset(&source).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "the assertion is not there")
+        })?;
+        let literal = literal_offset(&source[start..], self.literal)
+            .map(|i| start + i)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("could not find `{}` in the assertion", self.literal),
+                )
+            })?;
+        let mut updated = String::with_capacity(source.len());
+        updated.push_str(&source[..literal]);
+        updated.push_str(&format!("{:#018x}", hash));
+        updated.push_str(&source[literal + self.literal.len()..]);
+        fs::write(&path, updated)
+    }
+
+    /// The byte offset of the assertion's line and column
+    fn offset(&self, source: &str) -> Option<usize> {
+        let line_start = if self.line <= 1 {
+            0
+        } else {
+            source
+                .match_indices('\n')
+                .nth(self.line as usize - 2)
+                .map(|(i, _)| i + 1)?
+        };
+        let column = source[line_start..]
+            .char_indices()
+            .nth(self.column.saturating_sub(1) as usize)
+            .map(|(i, _)| i)?;
+        Some(line_start + column)
+    }
+
+    /// The changes since the recorded shape, if it was recorded with the expected hash
+    #[cfg(feature = "serde")]
+    fn recorded_changes(&self, shape: &Shape) -> Option<Vec<String>> {
+        let json = fs::read_to_string(self.shape_path()).ok()?;
+        let old = crate::json::from_str(&json).ok()?;
+        if old.type_hash() != self.expected {
+            return None;
+        }
+        Some(
+            diff::diff(&old, shape)
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        )
+    }
+
+    /// The lines of the recorded structure that were removed or added, if it was
+    /// recorded with the expected hash
+    #[cfg(not(feature = "serde"))]
+    fn recorded_changes(&self, shape: &Shape) -> Option<Vec<String>> {
+        let text = fs::read_to_string(self.shape_path()).ok()?;
+        let mut lines = text.lines();
+        if lines.next()? != format!("{:#018x}", self.expected) {
+            return None;
+        }
+        let old: Vec<_> = lines.collect();
+        let new = structure(shape);
+        let removed = old
+            .iter()
+            .filter(|line| !new.iter().any(|new| new == *line))
+            .map(|line| format!("- {}", line));
+        let added = new
+            .iter()
+            .filter(|line| !old.contains(&line.as_str()))
+            .map(|line| format!("+ {}", line));
+        Some(removed.chain(added).collect())
+    }
+
+    fn record_shape(&self, shape: &Shape) -> io::Result<()> {
+        let path = self.shape_path();
+        let snapshot = snapshot(shape);
+        if fs::read_to_string(&path).ok().as_ref() == Some(&snapshot) {
+            return Ok(());
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, snapshot)
+    }
+}
+
+#[cfg(feature = "serde")]
+const SNAPSHOT_EXTENSION: &str = "json";
+
+#[cfg(not(feature = "serde"))]
+const SNAPSHOT_EXTENSION: &str = "txt";
+
+#[cfg(feature = "serde")]
+fn snapshot(shape: &Shape) -> String {
+    crate::json::to_string(shape)
+}
+
+/// The hash of the shape, followed by its structure
+#[cfg(not(feature = "serde"))]
+fn snapshot(shape: &Shape) -> String {
+    let mut snapshot = format!("{:#018x}\n", shape.type_hash());
+    for line in structure(shape) {
+        snapshot.push_str(&line);
+        snapshot.push('\n');
+    }
+    snapshot
+}
+
+/// The structure of a shape, with a line for each field and variant of the named types
+/// that it contains, such as `Message::LaunchMissiles.destination: String`
+fn structure(shape: &Shape) -> Vec<String> {
+    let mut path = diff::Path::default();
+    if let Some(name) = shape.type_name() {
+        path.0.push(Segment::Type(name.to_owned()));
+    }
+    let mut lines = Vec::new();
+    structure_of(shape, &mut path, &mut lines);
+    lines
+}
+
+fn structure_of(shape: &Shape, path: &mut diff::Path, lines: &mut Vec<String>) {
+    match shape {
+        Shape::Struct { fields, .. } => structure_of_fields(fields, path, lines),
+        Shape::Union { fields, .. } => {
+            for field in fields {
+                path.0.push(Segment::Field(field.name.clone()));
+                structure_of(&field.shape, path, lines);
+                path.0.pop();
+            }
+        }
+        Shape::Enum { variants, .. } => {
+            for variant in variants {
+                path.0.push(Segment::Variant(variant.name.clone()));
+                structure_of_fields(&variant.fields, path, lines);
+                path.0.pop();
+            }
+        }
+        _ if path.0.is_empty() => lines.push(shape.to_string()),
+        _ => lines.push(format!("{}: {}", path, shape)),
+    }
+}
+
+fn structure_of_fields(fields: &Fields, path: &mut diff::Path, lines: &mut Vec<String>) {
+    match fields {
+        Fields::Unit => lines.push(path.to_string()),
+        Fields::Named(fields) => {
+            for field in fields {
+                path.0.push(Segment::Field(field.name.clone()));
+                structure_of(&field.shape, path, lines);
+                path.0.pop();
+            }
+        }
+        Fields::Unnamed(fields) => {
+            for (index, field) in fields.iter().enumerate() {
+                path.0.push(Segment::Index(index));
+                structure_of(field, path, lines);
+                path.0.pop();
+            }
+        }
+    }
+}
+
+/// The byte offset of the expected hash in the source of an assertion, which is the
+/// literal after the top-level comma that ends the type. Searching for the literal
+/// itself could find it in the type, such as the `0` in `Msg0`.
+fn literal_offset(assertion: &str, literal: &str) -> Option<usize> {
+    let args = assertion.find(['(', '[', '{'])? + 1;
+    let mut depth = 0usize;
+    let mut prev = '\0';
+    let mut comma = None;
+    for (i, c) in assertion[args..].char_indices() {
+        match c {
+            '(' | '[' | '{' | '<' => depth += 1,
+            // The `>` of `->` in a function type doesn't close anything
+            '>' if prev == '-' => {}
+            ')' | ']' | '}' | '>' => depth = depth.checked_sub(1)?,
+            ',' if depth == 0 => {
+                comma = Some(args + i + 1);
+                break;
+            }
+            _ => {}
+        }
+        prev = c;
+    }
+    let comma = comma?;
+    let offset = comma + assertion[comma..].len() - assertion[comma..].trim_start().len();
+    assertion[offset..].starts_with(literal).then_some(offset)
+}
+
+/// The hash of a type is not the expected one
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    pub type_name: &'static str,
+    pub expected: u64,
+    pub found: u64,
+    /// The structure of the type, with a line for each field and variant
+    pub structure: Vec<String>,
+    /// The changes since the expected hash, if the shape was recorded along with it
+    pub changes: Option<Vec<String>>,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "the hash of `{}` has changed", self.type_name)?;
+        writeln!(f, "  expected: {:#018x}", self.expected)?;
+        writeln!(f, "     found: {:#018x}", self.found)?;
+        match &self.changes {
+            Some(changes) => {
+                writeln!(f, "changes:")?;
+                for change in changes {
+                    writeln!(f, "  {}", change)?;
+                }
+            }
+            None => {
+                writeln!(f, "the shape for the expected hash was not recorded")?;
+                writeln!(f, "structure:")?;
+                for line in &self.structure {
+                    writeln!(f, "  {}", line)?;
+                }
+            }
+        }
+        write!(f, "set {}=1 to update the expected hash", UPDATE_VAR)
+    }
+}
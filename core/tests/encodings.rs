@@ -0,0 +1,318 @@
+//! Every built-in implementation must give the same hash from its `ENCODING` as from
+//! its shape.
+use type_hash_core::encoding::assert_encoding_matches;
+
+macro_rules! assert_encodings_match {
+    ($($ty: ty,)*) => {
+        $(assert_encoding_matches::<$ty>();)*
+    };
+}
+
+#[test]
+fn primitives() {
+    assert_encodings_match!(
+        bool, char, u8, i8, u16, i16, u32, i32, u64, i64, u128, i128, usize, isize, f32, f64, str,
+    );
+}
+
+#[test]
+fn arrays_pointers_and_references() {
+    assert_encodings_match!(
+        [u8; 0],
+        [u8; 4],
+        [u8],
+        *const u8,
+        *mut u8,
+        *const str,
+        &'static u8,
+        &'static mut u8,
+        &'static str,
+        &'static mut [u8],
+    );
+}
+
+#[test]
+fn collections() {
+    assert_encodings_match!(
+        std::collections::BinaryHeap<u8>,
+        std::collections::BTreeSet<u8>,
+        std::collections::LinkedList<u8>,
+        std::collections::VecDeque<u8>,
+        std::vec::Vec<u8>,
+        std::collections::BTreeMap<u8, String>,
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn std_collections() {
+    use std::collections::hash_map::RandomState;
+    use std::collections::{HashMap, HashSet};
+    use type_hash_core::WithHasher;
+    assert_encodings_match!(
+        HashSet<u8>,
+        HashMap<u8, String>,
+        WithHasher<HashSet<u8, RandomState>>,
+        WithHasher<HashMap<u8, String, RandomState>>,
+    );
+}
+
+#[test]
+fn opaque_types() {
+    assert_encodings_match!(
+        std::any::TypeId,
+        std::cell::Cell<u8>,
+        std::cell::Ref<'static, u8>,
+        std::cell::RefCell<u8>,
+        std::cell::RefMut<'static, u8>,
+        std::cell::UnsafeCell<u8>,
+        std::cmp::Ordering,
+        std::cmp::Reverse<u8>,
+        std::ffi::c_void,
+        std::ffi::CStr,
+        std::hash::BuildHasherDefault<u8>,
+        std::marker::PhantomData<u8>,
+        std::mem::ManuallyDrop<u8>,
+        std::mem::MaybeUninit<u8>,
+        std::net::IpAddr,
+        std::net::Ipv4Addr,
+        std::net::Ipv6Addr,
+        std::net::SocketAddr,
+        std::net::SocketAddrV4,
+        std::net::SocketAddrV6,
+        std::num::FpCategory,
+        std::num::NonZeroI128,
+        std::num::NonZeroI16,
+        std::num::NonZeroI32,
+        std::num::NonZeroI64,
+        std::num::NonZeroI8,
+        std::num::NonZeroIsize,
+        std::num::NonZeroU128,
+        std::num::NonZeroU16,
+        std::num::NonZeroU32,
+        std::num::NonZeroU64,
+        std::num::NonZeroU8,
+        std::num::NonZeroUsize,
+        std::num::Wrapping<u8>,
+        std::ops::Bound<u8>,
+        std::ops::Range<u8>,
+        std::ops::RangeFrom<u8>,
+        std::ops::RangeInclusive<u8>,
+        std::ops::RangeFull,
+        std::ops::RangeTo<u8>,
+        std::ops::RangeToInclusive<u8>,
+        std::option::Option<u8>,
+        std::pin::Pin<u8>,
+        std::ptr::NonNull<u8>,
+        std::result::Result<u8, u8>,
+        std::sync::atomic::AtomicBool,
+        std::sync::atomic::AtomicI16,
+        std::sync::atomic::AtomicI32,
+        std::sync::atomic::AtomicI64,
+        std::sync::atomic::AtomicI8,
+        std::sync::atomic::AtomicIsize,
+        std::sync::atomic::AtomicPtr<u8>,
+        std::sync::atomic::AtomicU16,
+        std::sync::atomic::AtomicU32,
+        std::sync::atomic::AtomicU64,
+        std::sync::atomic::AtomicU8,
+        std::sync::atomic::AtomicUsize,
+        std::time::Duration,
+        std::borrow::Cow<'static, u8>,
+        std::boxed::Box<u8>,
+        std::ffi::CString,
+        std::rc::Rc<u8>,
+        std::rc::Weak<u8>,
+        std::string::String,
+        std::sync::Arc<u8>,
+        std::sync::Weak<u8>,
+        std::borrow::Cow<'static, str>,
+        std::boxed::Box<str>,
+        std::sync::Arc<[u8]>,
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn std_opaque_types() {
+    assert_encodings_match!(
+        std::collections::hash_map::DefaultHasher,
+        std::collections::hash_map::RandomState,
+        std::ffi::OsStr,
+        std::ffi::OsString,
+        std::path::Path,
+        std::path::PathBuf,
+        std::sync::mpsc::Receiver<u8>,
+        std::sync::mpsc::Sender<u8>,
+        std::sync::mpsc::SyncSender<u8>,
+        std::sync::Mutex<u8>,
+        std::sync::Once,
+        std::sync::RwLock<u8>,
+        std::sync::RwLockReadGuard<'static, u8>,
+        std::sync::RwLockWriteGuard<'static, u8>,
+        std::thread::Builder,
+        std::thread::JoinHandle<u8>,
+        std::thread::LocalKey<u8>,
+        std::thread::Thread,
+        std::thread::ThreadId,
+        std::time::Instant,
+        std::time::SystemTime,
+    );
+}
+
+#[test]
+fn tuples() {
+    assert_encodings_match!(
+        (),
+        (u8,),
+        (u8, u8),
+        (u8, u8, u8),
+        (u8, u8, u8, u8),
+        (u8, u8, u8, u8, u8),
+        (u8, u8, u8, u8, u8, u8),
+        (u8, u8, u8, u8, u8, u8, u8),
+        (u8, u8, u8, u8, u8, u8, u8, u8),
+        (u8, u8, u8, u8, u8, u8, u8, u8, u8),
+        (u8, u8, u8, u8, u8, u8, u8, u8, u8, u8),
+        (u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8),
+        (u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8),
+    );
+}
+
+#[test]
+fn function_pointers() {
+    assert_encodings_match!(
+        fn() -> u8,
+        unsafe fn() -> u8,
+        extern "C" fn() -> u8,
+        unsafe extern "C" fn() -> u8,
+        extern "system" fn() -> u8,
+        unsafe extern "system" fn() -> u8,
+        fn(u8) -> u8,
+        unsafe fn(u8) -> u8,
+        extern "C" fn(u8) -> u8,
+        unsafe extern "C" fn(u8) -> u8,
+        extern "system" fn(u8) -> u8,
+        unsafe extern "system" fn(u8) -> u8,
+        fn(u8, u8) -> u8,
+        unsafe fn(u8, u8) -> u8,
+        extern "C" fn(u8, u8) -> u8,
+        unsafe extern "C" fn(u8, u8) -> u8,
+        extern "system" fn(u8, u8) -> u8,
+        unsafe extern "system" fn(u8, u8) -> u8,
+        fn(u8, u8, u8) -> u8,
+        unsafe fn(u8, u8, u8) -> u8,
+        extern "C" fn(u8, u8, u8) -> u8,
+        unsafe extern "C" fn(u8, u8, u8) -> u8,
+        extern "system" fn(u8, u8, u8) -> u8,
+        unsafe extern "system" fn(u8, u8, u8) -> u8,
+        fn(u8, u8, u8, u8) -> u8,
+        unsafe fn(u8, u8, u8, u8) -> u8,
+        extern "C" fn(u8, u8, u8, u8) -> u8,
+        unsafe extern "C" fn(u8, u8, u8, u8) -> u8,
+        extern "system" fn(u8, u8, u8, u8) -> u8,
+        unsafe extern "system" fn(u8, u8, u8, u8) -> u8,
+        fn(u8, u8, u8, u8, u8) -> u8,
+        unsafe fn(u8, u8, u8, u8, u8) -> u8,
+        extern "C" fn(u8, u8, u8, u8, u8) -> u8,
+        unsafe extern "C" fn(u8, u8, u8, u8, u8) -> u8,
+        extern "system" fn(u8, u8, u8, u8, u8) -> u8,
+        unsafe extern "system" fn(u8, u8, u8, u8, u8) -> u8,
+        fn(u8, u8, u8, u8, u8, u8) -> u8,
+        unsafe fn(u8, u8, u8, u8, u8, u8) -> u8,
+        extern "C" fn(u8, u8, u8, u8, u8, u8) -> u8,
+        unsafe extern "C" fn(u8, u8, u8, u8, u8, u8) -> u8,
+        extern "system" fn(u8, u8, u8, u8, u8, u8) -> u8,
+        unsafe extern "system" fn(u8, u8, u8, u8, u8, u8) -> u8,
+        fn(u8, u8, u8, u8, u8, u8, u8) -> u8,
+        unsafe fn(u8, u8, u8, u8, u8, u8, u8) -> u8,
+        extern "C" fn(u8, u8, u8, u8, u8, u8, u8) -> u8,
+        unsafe extern "C" fn(u8, u8, u8, u8, u8, u8, u8) -> u8,
+        extern "system" fn(u8, u8, u8, u8, u8, u8, u8) -> u8,
+        unsafe extern "system" fn(u8, u8, u8, u8, u8, u8, u8) -> u8,
+        fn(u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        unsafe fn(u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        extern "C" fn(u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        unsafe extern "C" fn(u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        extern "system" fn(u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        unsafe extern "system" fn(u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        fn(u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        unsafe fn(u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        extern "C" fn(u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        unsafe extern "C" fn(u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        extern "system" fn(u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        unsafe extern "system" fn(u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        fn(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        unsafe fn(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        extern "C" fn(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        unsafe extern "C" fn(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        extern "system" fn(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        unsafe extern "system" fn(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        fn(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        unsafe fn(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        extern "C" fn(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        unsafe extern "C" fn(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        extern "system" fn(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        unsafe extern "system" fn(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        fn(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        unsafe fn(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        extern "C" fn(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        unsafe extern "C" fn(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        extern "system" fn(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+        unsafe extern "system" fn(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8,
+    );
+}
+
+#[test]
+fn closure_traits() {
+    assert_encodings_match!(
+        dyn Fn() -> u8,
+        dyn Fn() -> u8 + Send,
+        dyn Fn() -> u8 + Sync,
+        dyn Fn() -> u8 + Send + Sync,
+        dyn FnMut() -> u8,
+        dyn FnMut() -> u8 + Send,
+        dyn FnMut() -> u8 + Sync,
+        dyn FnMut() -> u8 + Send + Sync,
+        dyn FnOnce() -> u8,
+        dyn FnOnce() -> u8 + Send,
+        dyn FnOnce() -> u8 + Sync,
+        dyn FnOnce() -> u8 + Send + Sync,
+        dyn Fn(u8) -> u8,
+        dyn Fn(u8) -> u8 + Send,
+        dyn Fn(u8) -> u8 + Sync,
+        dyn Fn(u8) -> u8 + Send + Sync,
+        dyn FnMut(u8) -> u8,
+        dyn FnMut(u8) -> u8 + Send,
+        dyn FnMut(u8) -> u8 + Sync,
+        dyn FnMut(u8) -> u8 + Send + Sync,
+        dyn FnOnce(u8) -> u8,
+        dyn FnOnce(u8) -> u8 + Send,
+        dyn FnOnce(u8) -> u8 + Sync,
+        dyn FnOnce(u8) -> u8 + Send + Sync,
+        dyn Fn(u8, u8) -> u8,
+        dyn Fn(u8, u8) -> u8 + Send,
+        dyn Fn(u8, u8) -> u8 + Sync,
+        dyn Fn(u8, u8) -> u8 + Send + Sync,
+        dyn FnMut(u8, u8) -> u8,
+        dyn FnMut(u8, u8) -> u8 + Send,
+        dyn FnMut(u8, u8) -> u8 + Sync,
+        dyn FnMut(u8, u8) -> u8 + Send + Sync,
+        dyn FnOnce(u8, u8) -> u8,
+        dyn FnOnce(u8, u8) -> u8 + Send,
+        dyn FnOnce(u8, u8) -> u8 + Sync,
+        dyn FnOnce(u8, u8) -> u8 + Send + Sync,
+        dyn Fn(u8, u8, u8) -> u8,
+        dyn Fn(u8, u8, u8) -> u8 + Send,
+        dyn Fn(u8, u8, u8) -> u8 + Sync,
+        dyn Fn(u8, u8, u8) -> u8 + Send + Sync,
+        dyn FnMut(u8, u8, u8) -> u8,
+        dyn FnMut(u8, u8, u8) -> u8 + Send,
+        dyn FnMut(u8, u8, u8) -> u8 + Sync,
+        dyn FnMut(u8, u8, u8) -> u8 + Send + Sync,
+        dyn FnOnce(u8, u8, u8) -> u8,
+        dyn FnOnce(u8, u8, u8) -> u8 + Send,
+        dyn FnOnce(u8, u8, u8) -> u8 + Sync,
+        dyn FnOnce(u8, u8, u8) -> u8 + Send + Sync,
+    );
+}
@@ -1,10 +1,18 @@
-use either::Either;
-use proc_macro2::TokenStream;
+use proc_macro2::{Span, TokenStream};
 use quote::{quote, quote_spanned, ToTokens};
+use std::collections::HashSet;
 use syn::{
-    parse_macro_input, spanned::Spanned, Data, DataEnum, DataStruct, DataUnion, DeriveInput, Field,
-    Fields, GenericParam, Generics, Ident, ImplGenerics, Lit, Meta, MetaNameValue, Type,
-    TypeGenerics, WhereClause,
+    parse::{Parse, ParseStream},
+    parse_macro_input, parse_quote,
+    punctuated::Punctuated,
+    spanned::Spanned,
+    visit::{self, Visit},
+    visit_mut::{self, VisitMut},
+    Attribute, Data, DataEnum, DataStruct, DataUnion, DeriveInput, Field, Fields, FnArg,
+    GenericArgument, GenericParam, Generics, Ident, ItemTrait, Lifetime, Lit, LitStr, Meta,
+    MetaNameValue, NestedMeta, ParenthesizedGenericArguments, Pat, Path, PathArguments, ReturnType,
+    Signature, Token, TraitBound, TraitItem, Type, TypeBareFn, TypeParamBound, TypePath,
+    TypeReference, WhereClause, WherePredicate,
 };
 
 #[proc_macro_derive(TypeHash, attributes(type_hash))]
@@ -13,189 +21,1198 @@ pub fn derive_type_hash(input: proc_macro::TokenStream) -> proc_macro::TokenStre
     type_hash_impl(input).into()
 }
 
+#[proc_macro_attribute]
+pub fn trait_hash(
+    args: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let item = parse_macro_input!(input as ItemTrait);
+    let args =
+        parse_macro_input!(args with Punctuated::<ContainerArg, Token![,]>::parse_terminated);
+    let mut container = Container::default();
+    let impls = match trait_args(&args).and_then(|()| container.apply(args)) {
+        Ok(()) => trait_hash_impl(&container, &item),
+        Err(error) => error,
+    };
+    quote!(#item #impls).into()
+}
+
+/// Reject the arguments of `#[type_hash]` that mean nothing for a trait object, which
+/// are all but `name` and `crate`
+fn trait_args(args: &Punctuated<ContainerArg, Token![,]>) -> Result<(), TokenStream> {
+    for arg in args {
+        let meta = match arg {
+            ContainerArg::Crate(_) => continue,
+            ContainerArg::Meta(NestedMeta::Meta(meta)) if meta.path().is_ident("name") => continue,
+            ContainerArg::Meta(meta) => meta,
+        };
+        return Err(match meta {
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("register") => quote_spanned! {
+                path.span()=>
+                compile_error!("Trait objects can't be registered");
+            },
+            m => quote_spanned! {
+                m.span()=>
+                compile_error!("Only `name` and `crate` are supported by `#[trait_hash]`");
+            },
+        });
+    }
+    Ok(())
+}
+
 fn type_hash_impl(input: DeriveInput) -> TokenStream {
+    let container = match Container::from_attributes(&input.attrs) {
+        Ok(container) => container,
+        Err(error) => return error,
+    };
     match &input.data {
-        Data::Struct(data) => type_hash_struct(&input.ident, &input.generics, data),
-        Data::Enum(data) => type_hash_enum(&input.ident, &input.generics, data),
-        Data::Union(data) => type_hash_union(&input.ident, data),
+        Data::Struct(data) => type_hash_struct(&input.ident, &container, &input.generics, data),
+        Data::Enum(data) => type_hash_enum(
+            &input.ident,
+            &container,
+            &input.attrs,
+            &input.generics,
+            data,
+        ),
+        Data::Union(data) => type_hash_union(&input.ident, &container, &input.generics, data),
     }
 }
 
-fn type_hash_struct(ident: &Ident, generics: &Generics, data: &DataStruct) -> TokenStream {
-    let name = ident.to_string();
-    let fields = write_struct_field_hashes(&data.fields)
-        .into_iter()
-        .flatten();
-    let (impl_generics, ty_generics, where_clause) = split_generics(generics);
-    quote! {
-        impl#impl_generics type_hash::TypeHash for #ident#ty_generics #where_clause {
-            fn write_hash(hasher: &mut impl std::hash::Hasher) {
-                hasher.write(#name.as_bytes());
-                #(#fields)*
-            }
+/// The `#[type_hash]` attributes of the type itself
+#[derive(Default)]
+struct Container {
+    /// Add the type to `type_hash::registry`
+    register: Option<Span>,
+    /// The name to hash the type by, instead of its identifier
+    name: Option<String>,
+    /// The path to the `type_hash` crate
+    krate: Option<Path>,
+    /// The bounds of the impls, instead of the ones inferred from the fields
+    bound: Option<Vec<WherePredicate>>,
+}
+
+/// An argument of a `#[type_hash]` attribute on a type. `crate` is parsed separately
+/// because its value can be a path, such as `$crate::type_hash`, instead of a string.
+enum ContainerArg {
+    Crate(Path),
+    Meta(NestedMeta),
+}
+
+impl Parse for ContainerArg {
+    fn parse(input: ParseStream) -> syn::Result<ContainerArg> {
+        if input.peek(Token![crate]) && input.peek2(Token![=]) {
+            input.parse::<Token![crate]>()?;
+            input.parse::<Token![=]>()?;
+            let path = if input.peek(LitStr) {
+                input.parse::<LitStr>()?.parse()?
+            } else {
+                input.call(Path::parse_mod_style)?
+            };
+            Ok(ContainerArg::Crate(path))
+        } else {
+            input.parse().map(ContainerArg::Meta)
         }
     }
 }
 
-fn type_hash_enum(ident: &Ident, generics: &Generics, data: &DataEnum) -> TokenStream {
-    let name = ident.to_string();
-    let (impl_generics, ty_generics, where_clause) = split_generics(generics);
-    let variants = data.variants.iter().flat_map(|v| {
-        v.discriminant
-            .iter()
-            .map(|(_, discriminant)| {
-                quote! {
-                    std::hash::Hash::hash(&(#discriminant as isize), hasher);
+impl Container {
+    fn from_attributes(attrs: &[Attribute]) -> Result<Container, TokenStream> {
+        let mut container = Container::default();
+        for att in attrs.iter().filter(|att| att.path.is_ident("type_hash")) {
+            match att.parse_args_with(Punctuated::<ContainerArg, Token![,]>::parse_terminated) {
+                Ok(metas) => container.apply(metas)?,
+                Err(e) => {
+                    let e = e.to_string();
+                    return Err(quote_spanned! {
+                        att.span()=>
+                        compile_error!("{}", #e);
+                    });
                 }
-            })
-            .chain(write_struct_field_hashes(&v.fields).into_iter().flatten())
-    });
-    quote! {
-        impl#impl_generics type_hash::TypeHash for #ident#ty_generics #where_clause{
-            fn write_hash(hasher: &mut impl std::hash::Hasher) {
-                hasher.write(#name.as_bytes());
-                #(#variants)*
             }
         }
+        Ok(container)
+    }
+
+    fn apply(&mut self, args: Punctuated<ContainerArg, Token![,]>) -> Result<(), TokenStream> {
+        for arg in args {
+            let meta = match arg {
+                ContainerArg::Crate(path) => {
+                    self.krate = Some(path);
+                    continue;
+                }
+                ContainerArg::Meta(meta) => meta,
+            };
+            match meta {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("register") => {
+                    self.register = Some(path.span());
+                }
+                NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                    path,
+                    lit: Lit::Str(name),
+                    ..
+                })) if path.is_ident("name") => {
+                    self.name = Some(name.value());
+                }
+                NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                    path,
+                    lit: Lit::Str(bound),
+                    ..
+                })) if path.is_ident("bound") => {
+                    self.bound = Some(parse_bound(&bound)?);
+                }
+                m => {
+                    return Err(quote_spanned! {
+                        m.span()=>
+                        compile_error!("Unsupported metadata");
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The name of the type in its hash
+    fn name(&self, ident: &Ident) -> String {
+        self.name.clone().unwrap_or_else(|| ident.to_string())
+    }
+
+    /// Put the generated items in an anonymous const, where the `type_hash` crate is
+    /// imported as `__type_hash`, so that they can refer to it by any path
+    fn wrap(&self, items: TokenStream) -> TokenStream {
+        let krate = match &self.krate {
+            Some(krate) => krate.to_token_stream(),
+            None => quote!(type_hash),
+        };
+        quote! {
+            const _: () = {
+                use #krate as __type_hash;
+                #items
+            };
+        }
     }
 }
 
-fn write_struct_field_hashes(fields: &Fields) -> Option<impl Iterator<Item = TokenStream> + '_> {
-    match &fields {
-        Fields::Unit => None,
-        Fields::Named(fields) => {
-            Some(Either::Left(fields.named.iter().map(
-                |f| match write_field_hash(f) {
-                    Ok(Some(tokens)) => tokens,
-                    Ok(None) => TokenStream::new(),
-                    Err(tokens) => tokens,
-                },
-            )))
-        }
-        Fields::Unnamed(fields) => {
-            Some(Either::Right(fields.unnamed.iter().map(
-                |f| match write_field_hash(f) {
-                    Ok(Some(tokens)) => tokens,
-                    Ok(None) => TokenStream::new(),
-                    Err(tokens) => tokens,
-                },
-            )))
+fn type_hash_struct(
+    ident: &Ident,
+    container: &Container,
+    generics: &Generics,
+    data: &DataStruct,
+) -> TokenStream {
+    let name = container.name(ident);
+    let mut encoding = Encoding::default();
+    let fields = encode_fields(&mut encoding, Some(&name), &data.fields);
+    let shape = quote! {
+        __type_hash::shape::Shape::Struct {
+            name: #name.into(),
+            fields: #fields,
+        }
+    };
+    impl_type_hash(ident, container, generics, shape, encoding)
+}
+
+fn type_hash_enum(
+    ident: &Ident,
+    container: &Container,
+    attrs: &[Attribute],
+    generics: &Generics,
+    data: &DataEnum,
+) -> TokenStream {
+    let name = container.name(ident);
+    let discriminant_type = discriminant_type(attrs);
+    let mut encoding = Encoding::default();
+    encoding.tag("Enum");
+    encoding.str(&name);
+    let mut hashed = Vec::new();
+    for v in &data.variants {
+        match VariantAttrs::from_attributes(&v.attrs) {
+            Ok(attrs) if attrs.skip => {}
+            Ok(attrs) => hashed.push((v, attrs)),
+            Err(error) => encoding.error(error),
         }
     }
+    encoding.len(hashed.len());
+    let mut variants = Vec::new();
+    for (index, (v, attrs)) in hashed.into_iter().enumerate() {
+        let variant_name = attrs.rename.unwrap_or_else(|| v.ident.to_string());
+        encoding.tag("Variant");
+        encoding.len(index);
+        encoding.str(&variant_name);
+        let discriminant = match &v.discriminant {
+            Some((_, discriminant)) => {
+                let value = widen_int(&discriminant_type, quote!(DISCRIMINANT));
+                let discriminant = quote! {{
+                    const DISCRIMINANT: #discriminant_type = #discriminant;
+                    #value
+                }};
+                encoding.discriminant(&discriminant);
+                quote! { Some(#discriminant) }
+            }
+            None => quote! { None },
+        };
+        let fields = match &attrs.as_type {
+            Some(ty) => {
+                encoding.tag("TupleStruct");
+                encoding.len(1);
+                encoding.tag("Field");
+                let shape = encoding.ty(ty);
+                quote! { __type_hash::shape::Fields::Unnamed([#shape].into()) }
+            }
+            None => encode_fields(&mut encoding, None, &v.fields),
+        };
+        variants.push(quote! {
+            __type_hash::shape::Variant {
+                name: #variant_name.into(),
+                discriminant: #discriminant,
+                fields: #fields,
+            }
+        });
+    }
+    let shape = quote! {
+        __type_hash::shape::Shape::Enum {
+            name: #name.into(),
+            variants: [#(#variants),*].into(),
+        }
+    };
+    impl_type_hash(ident, container, generics, shape, encoding)
 }
 
-// TODO: This is gnarly. Use something like darling to parse the attributes more cleanly
-fn write_field_hash(field: &Field) -> Result<Option<TokenStream>, TokenStream> {
-    let field_name = field
-        .ident
-        .as_ref()
-        .map(|name| {
-            let name = name.to_token_stream().to_string();
-            quote! { hasher.write(#name.as_bytes()); }
+fn impl_type_hash(
+    ident: &Ident,
+    container: &Container,
+    generics: &Generics,
+    shape: TokenStream,
+    encoding: Encoding,
+) -> TokenStream {
+    let (shape, encoding) = with_const_args(generics, shape, encoding);
+    let bounds = match &container.bound {
+        Some(bound) => bound.clone(),
+        None => infer_bounds(generics, &encoding),
+    };
+    let Encoding { pieces, errors, .. } = encoding;
+    let (impl_generics, ty_generics, _) = generics.split_for_impl();
+    let where_clause = DeriveWhereClause {
+        where_clause: generics.where_clause.as_ref(),
+        bounds: bounds.clone(),
+    };
+    let marker = Ident::new("__TypeHashMarker", Span::call_site());
+    let mut encoding_generics = generics.clone();
+    encoding_generics
+        .params
+        .push(GenericParam::Type(parse_quote! { #marker: ?Sized }));
+    let (encoding_impl_generics, _, _) = encoding_generics.split_for_impl();
+    let encoding_where_clause = DeriveWhereClause {
+        where_clause: generics.where_clause.as_ref(),
+        bounds: bounds.into_iter().map(encoding_bound).collect(),
+    };
+    let registration = registration(ident, container, generics);
+    container.wrap(quote! {
+        impl#encoding_impl_generics __type_hash::TypeEncoding<#marker> for #ident#ty_generics #encoding_where_clause {
+            const ENCODING: &'static [__type_hash::encoding::Piece] = &[#pieces];
+        }
+
+        impl#impl_generics __type_hash::TypeHash for #ident#ty_generics #where_clause {
+            fn shape_within(ancestors: &__type_hash::Ancestors<'_>) -> __type_hash::shape::Shape {
+                ancestors.enter::<Self, _>(|ancestors| #shape)
+            }
+        }
+
+        #registration
+        #errors
+    })
+}
+
+/// Wrap the shape of a type with const parameters in `Shape::ConstArgs`, so that their
+/// values are part of its hash
+fn with_const_args(
+    generics: &Generics,
+    shape: TokenStream,
+    encoding: Encoding,
+) -> (TokenStream, Encoding) {
+    let params: Vec<_> = generics.const_params().collect();
+    if params.is_empty() {
+        return (shape, encoding);
+    }
+    let mut wrapped = Encoding::default();
+    wrapped.tag("ConstArgs");
+    wrapped.len(params.len());
+    let mut args = Vec::new();
+    for param in params {
+        let ident = &param.ident;
+        let ty = match &param.ty {
+            Type::Path(ty) if ty.qself.is_none() => ty.path.get_ident(),
+            _ => None,
+        };
+        let arg = match ty {
+            Some(ty) if ty == "bool" => quote! { __type_hash::shape::ConstArg::Bool(#ident) },
+            Some(ty) if ty == "char" => quote! { __type_hash::shape::ConstArg::Char(#ident) },
+            Some(ty) if INT_TYPES.iter().any(|int| ty == int) => {
+                let value = widen_int(ty, quote!(#ident));
+                quote! { __type_hash::shape::ConstArg::Int(#value) }
+            }
+            _ => {
+                wrapped.error(quote_spanned! {
+                    param.ty.span()=>
+                    compile_error!("Unsupported type of const parameter");
+                });
+                continue;
+            }
+        };
+        wrapped.const_arg(&arg);
+        args.push(arg);
+    }
+    wrapped.extend(encoding);
+    let shape = quote! {
+        __type_hash::shape::Shape::ConstArgs {
+            args: [#(#args),*].into(),
+            shape: (#shape).into(),
+        }
+    };
+    (shape, wrapped)
+}
+
+/// Submit the type to `type_hash::registry`, if it is registered
+fn registration(ident: &Ident, container: &Container, generics: &Generics) -> TokenStream {
+    match container.register {
+        None => TokenStream::new(),
+        Some(span) if !generics.params.is_empty() => quote_spanned! {
+            span=>
+            compile_error!("Only types without generic parameters can be registered");
+        },
+        Some(_) => {
+            let name = container.name(ident);
+            quote! {
+                __type_hash::registry::submit! {
+                    __type_hash::registry::Registered::new::<#ident>(
+                        concat!(module_path!(), "::", #name)
+                    )
+                }
+            }
+        }
+    }
+}
+
+/// Convert an integer of type `ty` to `i128`, which is how discriminants and const
+/// arguments are hashed. A `u128` above `i128::MAX` would wrap around to a negative
+/// value, so it is a compile error instead. `value` must be the name of a const or a
+/// const parameter.
+fn widen_int(ty: &Ident, value: TokenStream) -> TokenStream {
+    if ty == "u128" {
+        quote! { __type_hash::encoding::U128::<#value>::I128 }
+    } else {
+        quote! { #value as i128 }
+    }
+}
+
+const INT_TYPES: &[&str] = &[
+    "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+];
+
+/// The type of an enum's discriminants, from its `#[repr]` attribute
+fn discriminant_type(attrs: &[Attribute]) -> Ident {
+    attrs
+        .iter()
+        .filter(|att| att.path.is_ident("repr"))
+        .filter_map(|att| match att.parse_meta() {
+            Ok(Meta::List(list)) => Some(list.nested),
+            _ => None,
         })
-        .unwrap_or_default();
-    for att in &field.attrs {
-        if let Some(name) = att.path.get_ident() {
-            if name == "type_hash" {
-                match att.parse_args() {
-                    Ok(Meta::NameValue(MetaNameValue { path, lit, .. })) => {
-                        if let (Some(name), Lit::Str(val)) = (path.get_ident(), lit) {
-                            if name == "as" {
-                                if let Ok(ty) = val.parse::<Type>() {
-                                    return Ok(Some(quote! {
-                                        #field_name
-                                        <#ty as type_hash::TypeHash>::write_hash(hasher);
-                                    }));
-                                } else {
-                                    return Err(quote_spanned! {
-                                        val.span()=>
-                                        compile_error!("Invalid type");
-                                    });
-                                }
-                            }
-                        }
-                        return Err(quote_spanned! {
-                            att.span()=>
-                            compile_error!("Unsupported metadata");
-                        });
-                    }
-                    Ok(Meta::Path(path)) => {
-                        if let Some(name) = path.get_ident() {
-                            if name == "skip" {
-                                return Ok(None);
-                            } else if name == "foreign_type" {
-                                let type_str = field.ty.to_token_stream().to_string();
-                                return Ok(Some(quote! {
-                                    #field_name
-                                    hasher.write(#type_str.as_bytes());
-                                }));
-                            }
-                        }
+        .flatten()
+        .find_map(|nested| match nested {
+            NestedMeta::Meta(Meta::Path(path)) => path
+                .get_ident()
+                .filter(|ident| INT_TYPES.iter().any(|ty| *ident == ty))
+                .cloned(),
+            _ => None,
+        })
+        .unwrap_or_else(|| Ident::new("isize", Span::call_site()))
+}
+
+/// A type's encoding as `Piece`s, for the const `ENCODING`, along with any errors in
+/// its attributes.
+///
+/// The pieces must match what `Shape::write_hash` writes for the shape that is built
+/// alongside them.
+#[derive(Default)]
+struct Encoding {
+    pieces: TokenStream,
+    errors: TokenStream,
+    /// The types that are hashed, from which the bounds of the impls are inferred
+    types: Vec<Type>,
+    /// Bounds given with `#[type_hash(bound = "...")]` on fields
+    bounds: Vec<WherePredicate>,
+}
+
+impl Encoding {
+    fn tag(&mut self, tag: &str) {
+        let tag = Ident::new(tag, Span::call_site());
+        self.pieces.extend(quote! {
+            __type_hash::encoding::Piece::Tag(__type_hash::encoding::Tag::#tag),
+        });
+    }
+
+    fn len(&mut self, len: usize) {
+        self.pieces.extend(quote! {
+            __type_hash::encoding::Piece::Len(#len),
+        });
+    }
+
+    fn str(&mut self, s: &str) {
+        self.pieces.extend(quote! {
+            __type_hash::encoding::Piece::Str(#s),
+        });
+    }
+
+    fn discriminant(&mut self, discriminant: &TokenStream) {
+        self.pieces.extend(quote! {
+            __type_hash::encoding::Piece::Discriminant(#discriminant),
+        });
+    }
+
+    fn const_arg(&mut self, arg: &TokenStream) {
+        self.pieces.extend(quote! {
+            __type_hash::encoding::Piece::Const(#arg),
+        });
+    }
+
+    /// Encode a type, returning an expression for its shape
+    fn ty(&mut self, ty: &Type) -> TokenStream {
+        let mut ty = ty.clone();
+        StaticFnLifetimes::default().visit_type_mut(&mut ty);
+        self.types.push(ty.clone());
+        self.pieces.extend(quote! {
+            __type_hash::encoding::Piece::Type(<#ty as __type_hash::TypeEncoding<__TypeHashMarker>>::ENCODING),
+        });
+        quote! { <#ty as __type_hash::TypeHash>::shape_within(ancestors) }
+    }
+
+    /// Encode a foreign type, returning an expression for its shape
+    fn foreign(&mut self, ty: &Type) -> TokenStream {
+        let name = ty.to_token_stream().to_string();
+        self.tag("Foreign");
+        self.str(&name);
+        quote! { __type_hash::shape::Shape::Foreign(#name.into()) }
+    }
+
+    /// Encode a type that is described by the function `with`, returning an expression
+    /// for its shape. Its encoding is only known at runtime.
+    fn custom(&mut self, ty: &Type, with: &Path) -> TokenStream {
+        let name = ty.to_token_stream().to_string();
+        self.pieces.extend(quote! {
+            __type_hash::encoding::Piece::Type(__type_hash::encoding::runtime_only()),
+        });
+        quote! {{
+            let mut hasher = __type_hash::hasher::BytesHasher::default();
+            #with(&mut hasher);
+            __type_hash::shape::Shape::Custom {
+                name: #name.into(),
+                bytes: hasher.into_bytes(),
+            }
+        }}
+    }
+
+    fn error(&mut self, error: TokenStream) {
+        self.errors.extend(error);
+    }
+
+    fn extend(&mut self, other: Encoding) {
+        self.pieces.extend(other.pieces);
+        self.errors.extend(other.errors);
+        self.types.extend(other.types);
+        self.bounds.extend(other.bounds);
+    }
+}
+
+/// Encode the kind of a struct or variant, its name if it has one, and then its fields,
+/// returning an expression for the shape of its fields
+fn encode_fields(encoding: &mut Encoding, name: Option<&str>, fields: &Fields) -> TokenStream {
+    encoding.tag(match fields {
+        Fields::Unit => "UnitStruct",
+        Fields::Named(_) => "Struct",
+        Fields::Unnamed(_) => "TupleStruct",
+    });
+    if let Some(name) = name {
+        encoding.str(name);
+    }
+    if let Fields::Unit = fields {
+        return quote! { __type_hash::shape::Fields::Unit };
+    }
+    let shapes = encode_field_list(encoding, fields);
+    if let Fields::Named(_) = fields {
+        quote! { __type_hash::shape::Fields::Named([#(#shapes),*].into()) }
+    } else {
+        quote! { __type_hash::shape::Fields::Unnamed([#(#shapes),*].into()) }
+    }
+}
+
+/// Encode the number of fields that are not skipped and then each of them, returning
+/// expressions for their shapes
+fn encode_field_list<'a>(
+    encoding: &mut Encoding,
+    fields: impl IntoIterator<Item = &'a Field>,
+) -> Vec<TokenStream> {
+    let fields_encoding: Vec<_> = fields
+        .into_iter()
+        .filter_map(|field| match encode_field(field) {
+            Ok(field_encoding) => field_encoding,
+            Err(error) => {
+                encoding.error(error);
+                None
+            }
+        })
+        .collect();
+    encoding.len(fields_encoding.len());
+    let mut shapes = Vec::new();
+    for (shape, field_encoding) in fields_encoding {
+        shapes.push(shape);
+        encoding.extend(field_encoding);
+    }
+    shapes
+}
+
+/// The `#[type_hash]` attributes of a field
+#[derive(Default)]
+struct FieldAttrs {
+    skip: bool,
+    /// How the field is hashed, if not as its type
+    kind: Option<FieldKind>,
+    /// The bounds of the impls for this field, instead of the ones inferred from its type
+    bound: Option<Vec<WherePredicate>>,
+}
+
+enum FieldKind {
+    Foreign,
+    Hasher,
+    As(Box<Type>),
+    With(Path),
+}
+
+impl FieldAttrs {
+    fn from_attributes(attrs: &[Attribute]) -> Result<FieldAttrs, TokenStream> {
+        let mut field = FieldAttrs::default();
+        for meta in type_hash_metas(attrs)? {
+            let kind = match meta {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("skip") => {
+                    field.skip = true;
+                    continue;
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("foreign_type") => {
+                    (path.span(), FieldKind::Foreign)
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("hasher") => {
+                    (path.span(), FieldKind::Hasher)
+                }
+                NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                    path,
+                    lit: Lit::Str(val),
+                    ..
+                })) if path.is_ident("as") => {
+                    (path.span(), FieldKind::As(Box::new(parse_type(&val)?)))
+                }
+                NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                    path,
+                    lit: Lit::Str(val),
+                    ..
+                })) if path.is_ident("with") => match val.parse::<Path>() {
+                    Ok(with) => (path.span(), FieldKind::With(with)),
+                    Err(_) => {
                         return Err(quote_spanned! {
-                            name.span()=>
-                            compile_error!("Unsupported metadata");
+                            val.span()=>
+                            compile_error!("Invalid path");
                         });
                     }
-                    Ok(m) => {
-                        return Err(quote_spanned! {
-                            m.span()=>
-                            compile_error!("Unsupported metadata");
-                        });
+                },
+                NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                    path,
+                    lit: Lit::Str(bound),
+                    ..
+                })) if path.is_ident("bound") => {
+                    field.bound = Some(parse_bound(&bound)?);
+                    continue;
+                }
+                m => {
+                    return Err(quote_spanned! {
+                        m.span()=>
+                        compile_error!("Unsupported metadata");
+                    });
+                }
+            };
+            if field.kind.is_some() {
+                return Err(quote_spanned! {
+                    kind.0=>
+                    compile_error!("Only one of `as`, `foreign_type`, `hasher` and `with` can be used");
+                });
+            }
+            field.kind = Some(kind.1);
+        }
+        Ok(field)
+    }
+}
+
+/// The `#[type_hash]` attributes of an enum variant
+#[derive(Default)]
+struct VariantAttrs {
+    skip: bool,
+    /// The name to hash the variant by, instead of its identifier
+    rename: Option<String>,
+    /// The type to hash as the only field of the variant, instead of its fields
+    as_type: Option<Type>,
+}
+
+impl VariantAttrs {
+    fn from_attributes(attrs: &[Attribute]) -> Result<VariantAttrs, TokenStream> {
+        let mut variant = VariantAttrs::default();
+        for meta in type_hash_metas(attrs)? {
+            match meta {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("skip") => {
+                    variant.skip = true;
+                }
+                NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                    path,
+                    lit: Lit::Str(val),
+                    ..
+                })) if path.is_ident("rename") => {
+                    variant.rename = Some(val.value());
+                }
+                NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                    path,
+                    lit: Lit::Str(val),
+                    ..
+                })) if path.is_ident("as") => {
+                    variant.as_type = Some(parse_type(&val)?);
+                }
+                m => {
+                    return Err(quote_spanned! {
+                        m.span()=>
+                        compile_error!("Unsupported metadata");
+                    });
+                }
+            }
+        }
+        Ok(variant)
+    }
+}
+
+/// The arguments of all of the `#[type_hash]` attributes of a field or variant
+fn type_hash_metas(attrs: &[Attribute]) -> Result<Vec<NestedMeta>, TokenStream> {
+    let mut metas = Vec::new();
+    for att in attrs.iter().filter(|att| att.path.is_ident("type_hash")) {
+        match att.parse_args_with(Punctuated::<NestedMeta, Token![,]>::parse_terminated) {
+            Ok(args) => metas.extend(args),
+            Err(e) => {
+                let e = e.to_string();
+                return Err(quote_spanned! {
+                    att.span()=>
+                    compile_error!("{}", #e);
+                });
+            }
+        }
+    }
+    Ok(metas)
+}
+
+fn parse_type(ty: &LitStr) -> Result<Type, TokenStream> {
+    ty.parse().map_err(|_| {
+        quote_spanned! {
+            ty.span()=>
+            compile_error!("Invalid type");
+        }
+    })
+}
+
+/// Encode a field, returning an expression for its shape, or `None` if it is skipped
+fn encode_field(field: &Field) -> Result<Option<(TokenStream, Encoding)>, TokenStream> {
+    let attrs = FieldAttrs::from_attributes(&field.attrs)?;
+    if attrs.skip {
+        return Ok(None);
+    }
+    let mut encoding = Encoding::default();
+    encoding.tag("Field");
+    let name = field
+        .ident
+        .as_ref()
+        .map(|name| name.to_token_stream().to_string());
+    if let Some(name) = &name {
+        encoding.str(name);
+    }
+    let ty = &field.ty;
+    let shape = match &attrs.kind {
+        None => encoding.ty(ty),
+        Some(FieldKind::Foreign) => encoding.foreign(ty),
+        Some(FieldKind::Hasher) => encoding.ty(&parse_quote!(__type_hash::WithHasher<#ty>)),
+        Some(FieldKind::As(ty)) => encoding.ty(ty),
+        Some(FieldKind::With(with)) => encoding.custom(ty, with),
+    };
+    if let Some(bound) = attrs.bound {
+        encoding.types.clear();
+        encoding.bounds = bound;
+    }
+    let shape = match &name {
+        Some(name) => quote! {
+            __type_hash::shape::Field {
+                name: #name.into(),
+                shape: #shape,
+            }
+        },
+        None => shape,
+    };
+    Ok(Some((shape, encoding)))
+}
+
+fn type_hash_union(
+    ident: &Ident,
+    container: &Container,
+    generics: &Generics,
+    data: &DataUnion,
+) -> TokenStream {
+    let name = container.name(ident);
+    let mut encoding = Encoding::default();
+    encoding.tag("Union");
+    encoding.str(&name);
+    let fields = encode_field_list(&mut encoding, &data.fields.named);
+    let shape = quote! {
+        __type_hash::shape::Shape::Union {
+            name: #name.into(),
+            fields: [#(#fields),*].into(),
+        }
+    };
+    impl_type_hash(ident, container, generics, shape, encoding)
+}
+
+/// Implement `TypeHash` for `dyn Trait`, with and without `Send` and `Sync`, from the
+/// trait's name, supertraits and the signatures of the methods that can be called on a
+/// trait object
+fn trait_hash_impl(container: &Container, item: &ItemTrait) -> TokenStream {
+    let ident = &item.ident;
+    if !item.generics.params.is_empty() {
+        return quote_spanned! {
+            item.generics.span()=>
+            compile_error!("Only traits without generic parameters are supported");
+        };
+    }
+    let name = container.name(ident);
+    let mut encoding = Encoding::default();
+    encoding.tag("Trait");
+    encoding.str(&name);
+    encoding.len(item.supertraits.len());
+    let supertraits: Vec<_> = item
+        .supertraits
+        .iter()
+        .map(|bound| encode_supertrait(&mut encoding, bound))
+        .collect();
+    let mut methods = Vec::new();
+    let mut method_encoding = Encoding::default();
+    for trait_item in &item.items {
+        let sig = match trait_item {
+            TraitItem::Method(method) if !requires_sized(&method.sig) => &method.sig,
+            TraitItem::Method(_) => continue,
+            TraitItem::Type(ty) => {
+                return quote_spanned! {
+                    ty.span()=>
+                    compile_error!("Traits with associated types are not supported");
+                }
+            }
+            other => {
+                return quote_spanned! {
+                    other.span()=>
+                    compile_error!("Only methods are supported in traits");
+                }
+            }
+        };
+        match encode_method(&mut method_encoding, sig) {
+            Ok(method) => methods.push(method),
+            Err(error) => return error,
+        }
+    }
+    encoding.len(methods.len());
+    encoding.extend(method_encoding);
+    let Encoding { pieces, errors, .. } = encoding;
+    let shape = quote! {
+        __type_hash::shape::Shape::Trait {
+            name: #name.into(),
+            supertraits: [#(#supertraits),*].into(),
+            methods: [#(#methods),*].into(),
+            auto_traits: auto_traits.iter().map(|auto_trait| (*auto_trait).into()).collect(),
+        }
+    };
+    let mut impls = TokenStream::new();
+    for auto_traits in [&[][..], &["Send"], &["Sync"], &["Send", "Sync"]] {
+        let len = auto_traits.len();
+        let bounds = auto_traits.iter().map(|auto_trait| {
+            let auto_trait = Ident::new(auto_trait, Span::call_site());
+            quote!(+ #auto_trait)
+        });
+        let ty = quote!(dyn #ident #(#bounds)* + '__type_hash);
+        impls.extend(quote! {
+            impl<'__type_hash, __TypeHashMarker: ?Sized> __type_hash::TypeEncoding<__TypeHashMarker> for #ty {
+                const ENCODING: &'static [__type_hash::encoding::Piece] = &[
+                    #pieces
+                    __type_hash::encoding::Piece::Len(#len),
+                    #(__type_hash::encoding::Piece::Str(#auto_traits),)*
+                ];
+            }
+
+            impl<'__type_hash> __type_hash::TypeHash for #ty {
+                fn shape_within(ancestors: &__type_hash::Ancestors<'_>) -> __type_hash::shape::Shape {
+                    let auto_traits: &[&str] = &[#(#auto_traits),*];
+                    ancestors.enter::<Self, _>(|ancestors| #shape)
+                }
+            }
+        });
+    }
+    container.wrap(quote! {
+        #impls
+        #errors
+    })
+}
+
+/// Supertraits from the standard library, which are only hashed by name
+const STD_SUPERTRAITS: &[&str] = &[
+    "Send",
+    "Sync",
+    "Unpin",
+    "UnwindSafe",
+    "RefUnwindSafe",
+    "Any",
+    "Debug",
+    "Display",
+    "Error",
+];
+
+/// Encode a supertrait, returning an expression for its shape. Supertraits from the
+/// standard library and lifetimes are encoded as foreign types with their names. Other
+/// supertraits must also be `#[trait_hash]` traits, and are encoded as their trait
+/// objects, so that changing their methods changes the hash.
+fn encode_supertrait(encoding: &mut Encoding, bound: &TypeParamBound) -> TokenStream {
+    let name = match bound {
+        TypeParamBound::Lifetime(lifetime) => lifetime.to_string(),
+        TypeParamBound::Trait(bound) => match bound.path.segments.last() {
+            Some(segment)
+                if segment.arguments.is_empty()
+                    && STD_SUPERTRAITS.iter().any(|name| segment.ident == name) =>
+            {
+                segment.ident.to_string()
+            }
+            _ => {
+                let path = &bound.path;
+                return encoding.ty(&parse_quote!(dyn #path));
+            }
+        },
+    };
+    encoding.tag("Foreign");
+    encoding.str(&name);
+    quote! { __type_hash::shape::Shape::Foreign(#name.into()) }
+}
+
+/// The receiver of a method that takes `self` with its type given, written in the same
+/// way whatever the path to its type is, or `None` if it is not supported
+fn receiver_type(ty: &Type) -> Option<&'static str> {
+    fn is_self(ty: &Type) -> bool {
+        matches!(ty, Type::Path(ty) if ty.qself.is_none() && ty.path.is_ident("Self"))
+    }
+    match ty {
+        Type::Reference(ty) if is_self(&ty.elem) => Some(match ty.mutability {
+            Some(_) => "&mut self",
+            None => "&self",
+        }),
+        Type::Path(_) if is_self(ty) => Some("self"),
+        Type::Path(TypePath { qself: None, path }) => {
+            let segment = path.segments.last()?;
+            let arg = match &segment.arguments {
+                PathArguments::AngleBracketed(args) if args.args.len() == 1 => &args.args[0],
+                _ => return None,
+            };
+            let arg = match arg {
+                GenericArgument::Type(arg) => arg,
+                _ => return None,
+            };
+            match segment.ident.to_string().as_str() {
+                "Box" if is_self(arg) => Some("self: Box<Self>"),
+                "Rc" if is_self(arg) => Some("self: Rc<Self>"),
+                "Arc" if is_self(arg) => Some("self: Arc<Self>"),
+                "Pin" => match arg {
+                    Type::Reference(arg) if arg.mutability.is_some() && is_self(&arg.elem) => {
+                        Some("self: Pin<&mut Self>")
                     }
-                    Err(e) => {
-                        let e = e.to_string();
+                    _ => None,
+                },
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Whether a method has a `where Self: Sized` bound, so that it can't be called on a
+/// trait object
+fn requires_sized(sig: &Signature) -> bool {
+    sig.generics
+        .where_clause
+        .iter()
+        .flat_map(|where_clause| &where_clause.predicates)
+        .any(|predicate| match predicate {
+            WherePredicate::Type(predicate) => {
+                predicate.bounded_ty.to_token_stream().to_string() == "Self"
+                    && predicate.bounds.iter().any(|bound| {
+                        matches!(bound, TypeParamBound::Trait(bound) if bound.path.is_ident("Sized"))
+                    })
+            }
+            _ => false,
+        })
+}
+
+/// Encode the signature of a method, returning an expression for its `Method`
+fn encode_method(encoding: &mut Encoding, sig: &Signature) -> Result<TokenStream, TokenStream> {
+    if let Some(abi) = &sig.abi {
+        if !matches!(&abi.name, Some(name) if name.value() == "Rust") {
+            return Err(quote_spanned! {
+                abi.span()=>
+                compile_error!("Only methods with the Rust ABI are supported");
+            });
+        }
+    }
+    // The method's own lifetimes are not in scope in the impls, and don't affect the hash
+    let mut lifetimes = StaticLifetimes(
+        sig.generics
+            .lifetimes()
+            .map(|def| def.lifetime.ident.clone())
+            .collect(),
+    );
+    let mut receiver = None;
+    let mut args = Vec::new();
+    for arg in &sig.inputs {
+        match arg {
+            FnArg::Receiver(r) => {
+                receiver = Some(match &r.reference {
+                    None => "self",
+                    Some(_) if r.mutability.is_some() => "&mut self",
+                    Some(_) => "&self",
+                });
+            }
+            FnArg::Typed(arg) if matches!(&*arg.pat, Pat::Ident(pat) if pat.ident == "self") => {
+                match receiver_type(&arg.ty) {
+                    Some(ty) => receiver = Some(ty),
+                    None => {
                         return Err(quote_spanned! {
-                            att.span()=>
-                            compile_error!("{}", #e);
-                        });
+                            arg.ty.span()=>
+                            compile_error!("Only `Self`, `&Self`, `&mut Self`, `Box<Self>`, `Rc<Self>`, `Arc<Self>` and `Pin<&mut Self>` are supported as the type of `self`");
+                        })
                     }
                 }
             }
+            FnArg::Typed(arg) => {
+                let mut ty = (*arg.ty).clone();
+                lifetimes.visit_type_mut(&mut ty);
+                args.push(ty);
+            }
         }
     }
-    let field_type = &field.ty;
-    Ok(Some(quote! {
-        #field_name
-        <#field_type as type_hash::TypeHash>::write_hash(hasher);
-    }))
+    let mut output = match &sig.output {
+        ReturnType::Default => parse_quote!(()),
+        ReturnType::Type(_, ty) => (**ty).clone(),
+    };
+    lifetimes.visit_type_mut(&mut output);
+    let name = sig.ident.to_string();
+    let is_unsafe = sig.unsafety.is_some();
+    let receiver_str = receiver.unwrap_or_default();
+    let receiver = match &receiver {
+        Some(receiver) => quote!(Some(#receiver.into())),
+        None => quote!(None),
+    };
+    encoding.tag("Method");
+    encoding.str(&name);
+    encoding.str(receiver_str);
+    encoding.const_arg(&quote!(__type_hash::shape::ConstArg::Bool(#is_unsafe)));
+    encoding.len(args.len());
+    let args: Vec<_> = args.iter().map(|arg| encoding.ty(arg)).collect();
+    let output = encoding.ty(&output);
+    Ok(quote! {
+        __type_hash::shape::Method {
+            name: #name.into(),
+            receiver: #receiver,
+            is_unsafe: #is_unsafe,
+            args: [#(#args),*].into(),
+            output: #output,
+        }
+    })
+}
+
+/// Makes the lifetimes in function pointers and `Fn` trait objects `'static`. Their
+/// elided and `for<'a>` lifetimes make them generic over those lifetimes, and `TypeHash`
+/// is only implemented for function types that are not, but lifetimes don't affect the
+/// hash anyway.
+#[derive(Default)]
+struct StaticFnLifetimes {
+    in_fn: bool,
 }
 
-// TODO Support unions
-fn type_hash_union(_ident: &Ident, data: &DataUnion) -> TokenStream {
-    quote_spanned! {
-        data.union_token.span()=>
-        compile_error!("Unions are not currently supported");
+impl StaticFnLifetimes {
+    fn within_fn(&mut self, visit: impl FnOnce(&mut Self)) {
+        let outer = std::mem::replace(&mut self.in_fn, true);
+        visit(self);
+        self.in_fn = outer;
     }
 }
 
-struct DeriveWhereClause<'a>(&'a Generics, Option<&'a WhereClause>);
+impl VisitMut for StaticFnLifetimes {
+    fn visit_type_bare_fn_mut(&mut self, ty: &mut TypeBareFn) {
+        ty.lifetimes = None;
+        self.within_fn(|this| visit_mut::visit_type_bare_fn_mut(this, ty));
+    }
+
+    fn visit_trait_bound_mut(&mut self, bound: &mut TraitBound) {
+        let is_fn =
+            bound.path.segments.last().is_some_and(|segment| {
+                matches!(segment.arguments, PathArguments::Parenthesized(_))
+            });
+        if is_fn {
+            bound.lifetimes = None;
+        }
+        visit_mut::visit_trait_bound_mut(self, bound);
+    }
+
+    fn visit_parenthesized_generic_arguments_mut(
+        &mut self,
+        args: &mut ParenthesizedGenericArguments,
+    ) {
+        self.within_fn(|this| visit_mut::visit_parenthesized_generic_arguments_mut(this, args));
+    }
+
+    fn visit_type_reference_mut(&mut self, ty: &mut TypeReference) {
+        if self.in_fn && ty.lifetime.is_none() {
+            ty.lifetime = Some(Lifetime::new("'static", ty.and_token.span()));
+        }
+        visit_mut::visit_type_reference_mut(self, ty);
+    }
+
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        if self.in_fn {
+            *lifetime = Lifetime::new("'static", lifetime.span());
+        }
+    }
+}
+
+/// Replaces the given lifetimes with `'static`
+struct StaticLifetimes(HashSet<Ident>);
+
+impl VisitMut for StaticLifetimes {
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        if self.0.contains(&lifetime.ident) {
+            *lifetime = Lifetime::new("'static", lifetime.span());
+        }
+    }
+}
+
+/// Bounds of the impls, for the type parameters that are used in the hashed types, and
+/// for associated types of them, such as `T::Item`
+fn infer_bounds(generics: &Generics, encoding: &Encoding) -> Vec<WherePredicate> {
+    let params: HashSet<_> = generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect();
+    let mut bounded = BoundedTypes {
+        params: &params,
+        types: Vec::new(),
+    };
+    for ty in &encoding.types {
+        bounded.visit_type(ty);
+    }
+    let mut seen = HashSet::new();
+    bounded
+        .types
+        .into_iter()
+        .filter(|ty| seen.insert(ty.to_token_stream().to_string()))
+        .map(|ty| parse_quote!(#ty: __type_hash::TypeHash))
+        .chain(encoding.bounds.iter().cloned())
+        .collect()
+}
+
+/// Finds the type parameters and their associated types that are used in a type
+struct BoundedTypes<'a> {
+    params: &'a HashSet<Ident>,
+    types: Vec<Type>,
+}
+
+impl<'a> BoundedTypes<'a> {
+    fn mentions_param(&self, ty: &Type) -> bool {
+        let mut inner = BoundedTypes {
+            params: self.params,
+            types: Vec::new(),
+        };
+        inner.visit_type(ty);
+        !inner.types.is_empty()
+    }
+}
+
+impl<'a, 'ast> Visit<'ast> for BoundedTypes<'a> {
+    fn visit_type_path(&mut self, ty: &'ast TypePath) {
+        let is_param = match &ty.qself {
+            Some(qself) => self.mentions_param(&qself.ty),
+            None => ty
+                .path
+                .segments
+                .first()
+                .is_some_and(|first| self.params.contains(&first.ident)),
+        };
+        if is_param {
+            self.types.push(Type::Path(ty.clone()));
+        } else {
+            visit::visit_type_path(self, ty);
+        }
+    }
+}
+
+/// Parse the `where` predicates of a `bound` attribute
+fn parse_bound(bound: &LitStr) -> Result<Vec<WherePredicate>, TokenStream> {
+    match bound.parse_with(Punctuated::<WherePredicate, Token![,]>::parse_terminated) {
+        Ok(predicates) => Ok(predicates.into_iter().collect()),
+        Err(e) => {
+            let e = e.to_string();
+            Err(quote_spanned! {
+                bound.span()=>
+                compile_error!("{}", #e);
+            })
+        }
+    }
+}
+
+/// The bound for `TypeEncoding`, which is the same as the bound for `TypeHash`, with
+/// `TypeHash` replaced by `TypeEncoding`
+fn encoding_bound(mut predicate: WherePredicate) -> WherePredicate {
+    if let WherePredicate::Type(predicate) = &mut predicate {
+        for bound in &mut predicate.bounds {
+            if let TypeParamBound::Trait(trait_bound) = bound {
+                let is_type_hash = trait_bound
+                    .path
+                    .segments
+                    .last()
+                    .is_some_and(|segment| segment.ident == "TypeHash");
+                if is_type_hash {
+                    *bound = parse_quote!(__type_hash::TypeEncoding<__TypeHashMarker>);
+                }
+            }
+        }
+    }
+    predicate
+}
+
+struct DeriveWhereClause<'a> {
+    where_clause: Option<&'a WhereClause>,
+    bounds: Vec<WherePredicate>,
+}
 
 impl<'a> ToTokens for DeriveWhereClause<'a> {
     fn to_tokens(&self, tokens: &mut TokenStream) {
-        let generics = &self.0;
-        let mut predicates = self.1.iter().flat_map(|w| &w.predicates).peekable();
-        let mut params = generics
-            .params
+        let mut predicates = self
+            .where_clause
             .iter()
-            .filter_map(|param| {
-                if let GenericParam::Type(type_param) = param {
-                    Some(&type_param.ident)
-                } else {
-                    None
-                }
-            })
+            .flat_map(|w| &w.predicates)
+            .chain(&self.bounds)
             .peekable();
-        if params.peek().is_some() || predicates.next().is_some() {
+        if predicates.peek().is_some() {
             let clause = quote! {
-                where #(#params: type_hash::TypeHash,)* #(#predicates,)*
+                where #(#predicates,)*
             };
             clause.to_tokens(tokens);
         }
     }
 }
-
-fn split_generics(generics: &Generics) -> (ImplGenerics, TypeGenerics, DeriveWhereClause<'_>) {
-    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
-    let where_clause = DeriveWhereClause(&generics, where_clause);
-    (impl_generics, ty_generics, where_clause)
-}
@@ -1,2 +1,12 @@
-pub use type_hash_core::TypeHash;
-pub use type_hash_macros::TypeHash;
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "registry")]
+pub use type_hash_core::registry;
+#[cfg(feature = "std")]
+pub use type_hash_core::{assert_type_hash, snapshot, WithHasher};
+pub use type_hash_core::{
+    compat, diff, encoding, hasher, shape, Ancestors, TypeEncoding, TypeHash,
+};
+#[cfg(feature = "serde")]
+pub use type_hash_core::{json, lock};
+pub use type_hash_macros::{trait_hash, TypeHash};
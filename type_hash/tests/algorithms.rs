@@ -0,0 +1,86 @@
+#![allow(unused)]
+use std::hash::Hasher;
+use type_hash::hasher::Fnv128Hasher;
+use type_hash::TypeHash;
+
+// The encoding of `bool` is the `Primitive` tag and the name "bool":
+// 01 0400000000000000 626f6f6c
+
+#[test]
+fn fnv_64_test_vectors() {
+    assert_eq!(bool::type_hash(), 0xfc01_22bf_7c09_4258);
+    assert_eq!(v1::Message::type_hash(), 9948147650933082462);
+}
+
+#[test]
+fn fnv_128_test_vectors() {
+    assert_eq!(
+        bool::type_hash_128(),
+        0x19a3_7ba7_b048_b3ae_b6c8_ccfa_d04c_39f8
+    );
+    assert_eq!(
+        v1::Message::type_hash_128(),
+        0x53b7_369a_9825_21cd_0fb7_d91f_7871_142e
+    );
+}
+
+#[test]
+fn fnv_128_hasher_matches_reference_implementation() {
+    let hasher = Fnv128Hasher::default();
+    assert_eq!(
+        hasher.finish_128(),
+        0x6c62_272e_07bb_0142_62b8_2175_6295_c58d
+    );
+    let mut hasher = Fnv128Hasher::default();
+    hasher.write(b"a");
+    assert_eq!(
+        hasher.finish_128(),
+        0xd228_cb69_6f1a_8caf_7891_2b70_4e4a_8964
+    );
+}
+
+#[test]
+fn type_hash_with_fnv_is_same_as_type_hash() {
+    assert_eq!(
+        v1::Message::type_hash_with::<fnv::FnvHasher>(),
+        v1::Message::type_hash()
+    );
+}
+
+#[test]
+fn type_hash_with_different_hasher_gives_different_hash() {
+    assert_ne!(
+        v1::Message::type_hash_with::<Fnv128Hasher>(),
+        v1::Message::type_hash()
+    );
+    assert_eq!(
+        v1::Message::type_hash_with::<Fnv128Hasher>(),
+        v1::Message::type_hash_128() as u64
+    );
+}
+
+#[cfg(feature = "sha2")]
+#[test]
+fn sha256_test_vectors() {
+    fn hex(digest: [u8; 32]) -> String {
+        digest.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+    assert_eq!(
+        hex(bool::type_hash_sha256()),
+        "2314fadba775e07028e6bb7aa50b54b914b960ea7cc44f0f99cc812f0c15f6a5"
+    );
+    assert_eq!(
+        hex(v1::Message::type_hash_sha256()),
+        "419705223c04162998fc726c81d1adcdcb4f97d54128e7de4dc6f792aea861b7"
+    );
+}
+
+mod v1 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub enum Message {
+        LaunchMissiles { destination: String },
+        CancelMissiles,
+    }
+}
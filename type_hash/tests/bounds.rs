@@ -0,0 +1,105 @@
+#![allow(unused)]
+use std::marker::PhantomData;
+use type_hash::shape::{Field, Fields, Shape};
+use type_hash::TypeHash;
+
+/// Does not implement `TypeHash`
+pub struct Marker;
+
+pub trait Store {
+    type Key;
+    type Value;
+}
+
+pub struct Memory;
+
+impl Store for Memory {
+    type Key = u64;
+    type Value = String;
+}
+
+#[derive(TypeHash)]
+pub struct Handle<T> {
+    id: u64,
+    #[type_hash(skip)]
+    _p: PhantomData<T>,
+}
+
+#[derive(TypeHash)]
+pub struct Items<I: Iterator> {
+    items: Vec<I::Item>,
+}
+
+#[derive(TypeHash)]
+pub struct Entry<S: Store> {
+    key: <S as Store>::Key,
+    value: Option<S::Value>,
+}
+
+#[derive(TypeHash)]
+pub struct List<T> {
+    value: T,
+    next: Option<Box<List<T>>>,
+}
+
+#[derive(TypeHash)]
+#[type_hash(bound = "S::Key: TypeHash")]
+pub struct Keys<S: Store> {
+    keys: Vec<S::Key>,
+    #[type_hash(skip)]
+    store: S,
+}
+
+#[derive(TypeHash)]
+pub struct Described<T> {
+    #[type_hash(as = "String", bound = "")]
+    value: T,
+    count: u32,
+}
+
+#[test]
+fn skipped_fields_are_not_bounded() {
+    assert_eq!(Handle::<Marker>::type_hash(), Handle::<u8>::type_hash());
+    assert_eq!(Handle::<Marker>::TYPE_HASH, Handle::<Marker>::type_hash());
+}
+
+#[test]
+fn associated_types_are_bounded() {
+    assert_eq!(
+        Items::<std::vec::IntoIter<u8>>::shape(),
+        Shape::Struct {
+            name: "Items".into(),
+            fields: Fields::Named(vec![Field {
+                name: "items".into(),
+                shape: <Vec<u8>>::shape(),
+            }]),
+        }
+    );
+    assert_eq!(
+        Items::<std::vec::IntoIter<u8>>::TYPE_HASH,
+        Items::<std::vec::IntoIter<u8>>::type_hash()
+    );
+    assert_eq!(Entry::<Memory>::TYPE_HASH, Entry::<Memory>::type_hash());
+}
+
+#[test]
+fn recursive_generic_types() {
+    assert_ne!(List::<u8>::type_hash(), List::<u16>::type_hash());
+}
+
+#[test]
+fn container_bound() {
+    assert_eq!(Keys::<Memory>::TYPE_HASH, Keys::<Memory>::type_hash());
+}
+
+#[test]
+fn field_bound() {
+    assert_eq!(
+        Described::<Marker>::type_hash(),
+        Described::<u8>::type_hash()
+    );
+    assert_eq!(
+        Described::<Marker>::TYPE_HASH,
+        Described::<Marker>::type_hash()
+    );
+}
@@ -0,0 +1,108 @@
+#![cfg(feature = "registry")]
+#![allow(unused)]
+use type_hash::registry;
+use type_hash::TypeHash;
+
+#[test]
+fn types_with_the_same_hash_collide() {
+    let collisions = registry::collisions();
+    assert_eq!(collisions.len(), 1);
+    assert_eq!(collisions[0].hash, v1::Ping::type_hash());
+    let names: Vec<_> = collisions[0]
+        .types
+        .iter()
+        .map(|registered| registered.name())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "collisions::v1::Ping",
+            "collisions::v2::Ping",
+            "collisions::v3::Ping"
+        ]
+    );
+}
+
+#[test]
+fn types_with_the_same_name_are_duplicates() {
+    let duplicates = registry::duplicate_names();
+    assert_eq!(duplicates.len(), 1);
+    assert_eq!(duplicates[0].name, "collisions::v1::Pong");
+    let type_names: Vec<_> = duplicates[0]
+        .types
+        .iter()
+        .map(|registered| registered.type_name())
+        .collect();
+    assert_eq!(
+        type_names,
+        ["collisions::v1::Pong", "collisions::v1::WirePong"]
+    );
+    assert!(registry::by_name("collisions::v1::Pong").is_none());
+    assert!(registry::by_name("collisions::v2::Ping").is_some());
+}
+
+#[test]
+fn collisions_are_an_error() {
+    let error = registry::check_collisions().unwrap_err();
+    assert_eq!(error.hashes.len(), 1);
+    assert_eq!(error.names.len(), 1);
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "registered types have the same hash:
+{:#018x} is the hash of collisions::v1::Ping, collisions::v2::Ping, collisions::v3::Ping, which have the same structure
+registered types have the same name:
+collisions::v1::Pong is the name of collisions::v1::Pong, collisions::v1::WirePong",
+            v1::Ping::type_hash()
+        )
+    );
+}
+
+#[test]
+#[should_panic(expected = "registered types have the same hash")]
+fn assert_no_collisions_panics() {
+    registry::assert_no_collisions();
+}
+
+mod v1 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    #[type_hash(register)]
+    pub struct Ping {
+        id: u64,
+    }
+
+    #[derive(TypeHash)]
+    #[type_hash(register)]
+    pub struct Pong {
+        id: u64,
+    }
+
+    #[derive(TypeHash)]
+    #[type_hash(register, name = "Pong")]
+    pub struct WirePong {
+        id: u32,
+    }
+}
+
+mod v2 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    #[type_hash(register)]
+    pub struct Ping {
+        id: u64,
+    }
+}
+
+mod v3 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    #[type_hash(register)]
+    pub struct Ping {
+        #[type_hash(as = "u64")]
+        id: std::num::NonZeroU64,
+    }
+}
@@ -0,0 +1,351 @@
+#![allow(unused)]
+use type_hash::compat::{check, Compatibility, Format, Report, SemverBump};
+use type_hash::TypeHash;
+
+fn report<Old: TypeHash, New: TypeHash>(format: Format) -> Report {
+    check(&Old::shape(), &New::shape(), format)
+}
+
+fn compatibility<Old: TypeHash, New: TypeHash>(format: Format) -> Compatibility {
+    report::<Old, New>(format).compatibility
+}
+
+fn reasons<Old: TypeHash, New: TypeHash>(format: Format) -> Vec<String> {
+    report::<Old, New>(format)
+        .reasons
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+}
+
+const ALL_FORMATS: [Format; 4] = [
+    Format::Bincode,
+    Format::Json,
+    Format::MessagePack,
+    Format::Postcard,
+];
+
+#[test]
+fn same_shape_is_identical() {
+    for format in ALL_FORMATS {
+        let report = report::<v1::Person, v1::Person>(format);
+        assert_eq!(report.compatibility, Compatibility::Identical);
+        assert!(report.reasons.is_empty());
+        assert_eq!(report.semver_bump(), SemverBump::None);
+    }
+}
+
+#[test]
+fn renamed_field() {
+    assert_eq!(
+        compatibility::<v1::Person, v2::Person>(Format::Bincode),
+        Compatibility::Compatible
+    );
+    assert_eq!(
+        reasons::<v1::Person, v2::Person>(Format::Bincode),
+        ["Person.full_name: renamed name -> full_name: the names of fields are not written (Compatible)"]
+    );
+    assert_eq!(
+        compatibility::<v1::Person, v2::Person>(Format::Json),
+        Compatibility::Breaking
+    );
+    assert_eq!(
+        report::<v1::Person, v2::Person>(Format::Json).semver_bump(),
+        SemverBump::Major
+    );
+}
+
+#[test]
+fn added_trailing_option_field() {
+    assert_eq!(
+        compatibility::<v1::Person, v3::Person>(Format::Json),
+        Compatibility::Compatible
+    );
+    assert_eq!(
+        compatibility::<v1::Person, v3::Person>(Format::MessagePack),
+        Compatibility::Compatible
+    );
+    assert_eq!(
+        report::<v1::Person, v3::Person>(Format::Json).semver_bump(),
+        SemverBump::Patch
+    );
+    assert_eq!(
+        compatibility::<v1::Person, v3::Person>(Format::Bincode),
+        Compatibility::Breaking
+    );
+    assert_eq!(
+        compatibility::<v1::Person, v3::Person>(Format::Postcard),
+        Compatibility::Breaking
+    );
+}
+
+#[test]
+fn added_and_removed_required_fields() {
+    assert_eq!(
+        reasons::<v1::Person, v4::Person>(Format::Json),
+        [
+            "Person.age: removed: the new version skips the field, and the old version needs it (Backward)",
+            "Person.email: added: the new version needs it, and the old version skips it (Forward)",
+        ]
+    );
+    assert_eq!(
+        compatibility::<v1::Person, v4::Person>(Format::Json),
+        Compatibility::Breaking
+    );
+}
+
+#[test]
+fn reordered_fields() {
+    assert_eq!(
+        compatibility::<v1::Person, v5::Person>(Format::Json),
+        Compatibility::Compatible
+    );
+    assert_eq!(
+        compatibility::<v1::Person, v5::Person>(Format::Bincode),
+        Compatibility::Breaking
+    );
+}
+
+#[test]
+fn added_variant() {
+    for format in ALL_FORMATS {
+        let report = report::<v1::Command, v2::Command>(format);
+        assert_eq!(report.compatibility, Compatibility::Backward);
+        assert_eq!(report.semver_bump(), SemverBump::Minor);
+    }
+    assert_eq!(
+        reasons::<v1::Command, v2::Command>(Format::Bincode),
+        ["Command::Restart: added: the old version can't read it (Backward)"]
+    );
+}
+
+#[test]
+fn removed_variant() {
+    for format in ALL_FORMATS {
+        assert_eq!(
+            compatibility::<v2::Command, v1::Command>(format),
+            Compatibility::Forward
+        );
+    }
+}
+
+#[test]
+fn inserted_variant_moves_the_others_for_formats_that_write_indices() {
+    assert_eq!(
+        compatibility::<v1::Command, v3::Command>(Format::Json),
+        Compatibility::Backward
+    );
+    assert_eq!(
+        compatibility::<v1::Command, v3::Command>(Format::Bincode),
+        Compatibility::Breaking
+    );
+}
+
+#[test]
+fn changed_discriminant() {
+    for format in ALL_FORMATS {
+        assert_eq!(
+            compatibility::<v1::Level, v2::Level>(format),
+            Compatibility::Compatible
+        );
+    }
+}
+
+#[test]
+fn widened_integer() {
+    assert_eq!(
+        compatibility::<v1::Counter, v2::Counter>(Format::Json),
+        Compatibility::Backward
+    );
+    assert_eq!(
+        compatibility::<v1::Counter, v2::Counter>(Format::Postcard),
+        Compatibility::Backward
+    );
+    assert_eq!(
+        compatibility::<v1::Counter, v2::Counter>(Format::Bincode),
+        Compatibility::Breaking
+    );
+    // Single bytes are not varints in postcard
+    assert_eq!(
+        compatibility::<u8, u16>(Format::Postcard),
+        Compatibility::Breaking
+    );
+    assert_eq!(
+        compatibility::<u8, u16>(Format::Json),
+        Compatibility::Backward
+    );
+    assert_eq!(
+        compatibility::<u32, i64>(Format::Json),
+        Compatibility::Breaking
+    );
+}
+
+#[test]
+fn value_wrapped_in_option() {
+    assert_eq!(
+        compatibility::<v1::Counter, v3::Counter>(Format::Json),
+        Compatibility::Backward
+    );
+    assert_eq!(
+        compatibility::<v1::Counter, v3::Counter>(Format::Bincode),
+        Compatibility::Breaking
+    );
+}
+
+#[test]
+fn renamed_types_and_collections() {
+    for format in ALL_FORMATS {
+        assert_eq!(
+            compatibility::<v1::Person, v1::Human>(format),
+            Compatibility::Compatible
+        );
+        assert_eq!(
+            compatibility::<Vec<u8>, std::collections::VecDeque<u8>>(format),
+            Compatibility::Compatible
+        );
+    }
+}
+
+#[test]
+fn changed_mutability() {
+    for format in ALL_FORMATS {
+        let report = report::<&'static [u8], &'static mut [u8]>(format);
+        assert_eq!(report.compatibility, Compatibility::Compatible);
+        assert_eq!(report.semver_bump(), SemverBump::Patch);
+        assert_eq!(
+            compatibility::<*const u8, *mut u8>(format),
+            Compatibility::Compatible
+        );
+    }
+    assert_eq!(
+        reasons::<(&'static u8,), (&'static mut u8,)>(Format::Json),
+        [".0: &T -> &mut T: mutability doesn't change how the value is written (Compatible)"]
+    );
+    assert_eq!(
+        reasons::<*mut u8, *const u8>(Format::Bincode),
+        ["*mut T -> *const T: mutability doesn't change how the value is written (Compatible)"]
+    );
+}
+
+#[test]
+fn changed_type_is_breaking() {
+    for format in ALL_FORMATS {
+        assert_eq!(
+            compatibility::<v1::Counter, v4::Counter>(format),
+            Compatibility::Breaking
+        );
+    }
+    assert_eq!(
+        reasons::<v1::Counter, v4::Counter>(Format::Json),
+        ["Counter.count: u32 -> String (Breaking)"]
+    );
+}
+
+mod v1 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct Person {
+        name: String,
+        age: u8,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Human {
+        name: String,
+        age: u8,
+    }
+
+    #[derive(TypeHash)]
+    pub enum Command {
+        Start,
+        Stop { force: bool },
+    }
+
+    #[derive(TypeHash)]
+    pub enum Level {
+        Low = 1,
+        High = 2,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Counter {
+        count: u32,
+    }
+}
+
+mod v2 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct Person {
+        full_name: String,
+        age: u8,
+    }
+
+    #[derive(TypeHash)]
+    pub enum Command {
+        Start,
+        Stop { force: bool },
+        Restart,
+    }
+
+    #[derive(TypeHash)]
+    pub enum Level {
+        Low = 10,
+        High = 20,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Counter {
+        count: u64,
+    }
+}
+
+mod v3 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct Person {
+        name: String,
+        age: u8,
+        nickname: Option<String>,
+    }
+
+    #[derive(TypeHash)]
+    pub enum Command {
+        Start,
+        Pause,
+        Stop { force: bool },
+    }
+
+    #[derive(TypeHash)]
+    pub struct Counter {
+        count: Option<u32>,
+    }
+}
+
+mod v4 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct Person {
+        name: String,
+        email: String,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Counter {
+        count: String,
+    }
+}
+
+mod v5 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct Person {
+        age: u8,
+        name: String,
+    }
+}
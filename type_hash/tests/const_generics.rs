@@ -0,0 +1,148 @@
+#![allow(unused)]
+use type_hash::diff::diff;
+use type_hash::shape::{ConstArg, Fields, Shape};
+use type_hash::TypeHash;
+
+#[test]
+fn arrays_of_any_length() {
+    assert_eq!(
+        <[u8; 64]>::shape(),
+        Shape::Array {
+            len: 64,
+            element: Box::new(u8::shape()),
+        }
+    );
+    assert_eq!(<[f32; 256]>::TYPE_HASH, <[f32; 256]>::type_hash());
+    assert_ne!(<[u8; 64]>::type_hash(), <[u8; 65]>::type_hash());
+}
+
+#[test]
+fn const_arguments_are_part_of_the_shape() {
+    assert_eq!(
+        v1::Buf::<4>::shape(),
+        Shape::ConstArgs {
+            args: vec![ConstArg::Int(4)],
+            shape: Box::new(Shape::Struct {
+                name: "Buf".into(),
+                fields: Fields::Unnamed(vec![<Vec<u8>>::shape()]),
+            }),
+        }
+    );
+    assert_eq!(
+        v1::Flags::<true, 'x', -1>::shape().to_string(),
+        "Flags<true, 'x', -1>"
+    );
+}
+
+#[test]
+fn different_const_arguments_have_different_hashes() {
+    assert_ne!(v1::Buf::<4>::type_hash(), v1::Buf::<8>::type_hash());
+    assert_ne!(
+        v1::Flags::<true, 'x', -1>::type_hash(),
+        v1::Flags::<false, 'x', -1>::type_hash()
+    );
+    assert_ne!(
+        v1::Flags::<true, 'x', -1>::type_hash(),
+        v1::Flags::<true, 'y', -1>::type_hash()
+    );
+    assert_ne!(
+        v1::Flags::<true, 'x', -1>::type_hash(),
+        v1::Flags::<true, 'x', 1>::type_hash()
+    );
+}
+
+#[test]
+fn u128_arguments_up_to_i128_max() {
+    const MAX: u128 = i128::MAX as u128;
+    assert_eq!(
+        v1::Wide::<MAX>::shape(),
+        Shape::ConstArgs {
+            args: vec![ConstArg::Int(i128::MAX)],
+            shape: Box::new(Shape::Struct {
+                name: "Wide".into(),
+                fields: Fields::Unit,
+            }),
+        }
+    );
+    assert_eq!(v1::Wide::<MAX>::TYPE_HASH, v1::Wide::<MAX>::type_hash());
+}
+
+#[test]
+fn const_arguments_are_different_to_no_arguments() {
+    assert_ne!(v1::Buf::<0>::type_hash(), v2::Buf::type_hash());
+}
+
+#[test]
+fn const_type_hash_is_same_as_type_hash() {
+    assert_eq!(v1::Buf::<4>::TYPE_HASH, v1::Buf::<4>::type_hash());
+    assert_eq!(
+        v1::Flags::<true, 'x', -1>::TYPE_HASH,
+        v1::Flags::<true, 'x', -1>::type_hash()
+    );
+    assert_eq!(
+        v1::Matrix::<f64, 2, 3>::TYPE_HASH,
+        v1::Matrix::<f64, 2, 3>::type_hash()
+    );
+    assert_eq!(v1::Kind::<7>::TYPE_HASH, v1::Kind::<7>::type_hash());
+}
+
+#[test]
+fn type_and_const_parameters() {
+    assert_ne!(
+        v1::Matrix::<f64, 2, 3>::type_hash(),
+        v1::Matrix::<f64, 3, 2>::type_hash()
+    );
+    assert_ne!(
+        v1::Matrix::<f64, 2, 3>::type_hash(),
+        v1::Matrix::<f32, 2, 3>::type_hash()
+    );
+}
+
+#[test]
+fn diff_of_const_arguments() {
+    let changes: Vec<_> = diff(
+        &v1::Matrix::<u8, 2, 2>::shape(),
+        &v1::Matrix::<u8, 2, 3>::shape(),
+    )
+    .iter()
+    .map(ToString::to_string)
+    .collect();
+    assert_eq!(
+        changes,
+        [
+            "Matrix const arguments <2, 2> -> <2, 3>",
+            "Matrix.rows[_]: [u8; 2] -> [u8; 3]"
+        ]
+    );
+}
+
+mod v1 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct Buf<const N: usize>(Vec<u8>);
+
+    #[derive(TypeHash)]
+    pub struct Flags<const A: bool, const B: char, const C: i32>;
+
+    #[derive(TypeHash)]
+    pub struct Matrix<T, const R: usize, const C: usize> {
+        rows: [[T; C]; R],
+    }
+
+    #[derive(TypeHash)]
+    pub struct Wide<const N: u128>;
+
+    #[derive(TypeHash)]
+    pub enum Kind<const N: u8> {
+        A([u8; 2]),
+        B,
+    }
+}
+
+mod v2 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct Buf(Vec<u8>);
+}
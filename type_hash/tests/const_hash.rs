@@ -0,0 +1,166 @@
+#![cfg(feature = "std")]
+#![allow(unused)]
+use std::collections::HashMap;
+use type_hash::encoding::assert_encoding_matches;
+use type_hash::TypeHash;
+
+const MESSAGE_HASH: u64 = v1::Message::TYPE_HASH;
+const _: () = assert!(v1::Message::TYPE_HASH == 9948147650933082462);
+
+#[test]
+fn const_type_hash_is_same_as_type_hash_for_built_in_types() {
+    assert_eq!(u64::TYPE_HASH, u64::type_hash());
+    assert_eq!(str::TYPE_HASH, str::type_hash());
+    assert_eq!(<[u8; 4]>::TYPE_HASH, <[u8; 4]>::type_hash());
+    assert_eq!(<&mut [i32]>::TYPE_HASH, <&mut [i32]>::type_hash());
+    assert_eq!(<*const bool>::TYPE_HASH, <*const bool>::type_hash());
+    assert_eq!(
+        <(String, Vec<&'static str>)>::TYPE_HASH,
+        <(String, Vec<&'static str>)>::type_hash()
+    );
+    assert_eq!(
+        HashMap::<String, Option<Box<u8>>>::TYPE_HASH,
+        HashMap::<String, Option<Box<u8>>>::type_hash()
+    );
+}
+
+#[test]
+fn const_type_hash_is_same_as_type_hash_for_derived_types() {
+    assert_eq!(v1::Message::TYPE_HASH, v1::Message::type_hash());
+    assert_eq!(v1::Unit::TYPE_HASH, v1::Unit::type_hash());
+    assert_eq!(v1::Tuple::TYPE_HASH, v1::Tuple::type_hash());
+    assert_eq!(v1::Discriminants::TYPE_HASH, v1::Discriminants::type_hash());
+    assert_eq!(
+        v1::Generic::<u8, String>::TYPE_HASH,
+        v1::Generic::<u8, String>::type_hash()
+    );
+    assert_eq!(v1::Attributes::TYPE_HASH, v1::Attributes::type_hash());
+}
+
+#[test]
+fn const_type_hash_can_be_used_in_patterns() {
+    let kind = match v1::Message::type_hash() {
+        MESSAGE_HASH => "message",
+        _ => "unknown",
+    };
+    assert_eq!(kind, "message");
+}
+
+#[test]
+fn hand_written_encoding_matches_shape() {
+    assert_encoding_matches::<v1::Point>();
+    assert_encoding_matches::<v1::Message>();
+    assert_eq!(v1::Point::type_hash(), v2::Point::type_hash());
+}
+
+#[test]
+#[should_panic(
+    expected = "the `ENCODING` of `const_hash::v1::Mismatched` does not match its shape `Mismatched`"
+)]
+fn hand_written_encoding_that_does_not_match_shape() {
+    assert_encoding_matches::<v1::Mismatched>();
+}
+
+#[test]
+fn recursive_types_still_have_runtime_type_hash() {
+    assert_eq!(v1::Node::type_hash(), v1::Node::type_hash());
+}
+
+mod v1 {
+    use type_hash::encoding::{Piece, Tag};
+    use type_hash::shape::{Fields, Shape};
+    use type_hash::{Ancestors, TypeEncoding, TypeHash};
+
+    #[derive(TypeHash)]
+    pub enum Message {
+        LaunchMissiles { destination: String },
+        CancelMissiles,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Unit;
+
+    #[derive(TypeHash)]
+    pub struct Tuple(u8, (i64, [char; 3]));
+
+    #[derive(TypeHash)]
+    #[repr(u8)]
+    pub enum Discriminants {
+        A = 1,
+        B = 255,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Generic<A, B: Clone>
+    where
+        A: Copy,
+    {
+        a: A,
+        b: Vec<B>,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Attributes {
+        #[type_hash(skip)]
+        skipped: bool,
+        #[type_hash(as = "u32")]
+        renamed: u64,
+        #[type_hash(foreign_type)]
+        foreign: Foreign,
+    }
+
+    pub struct Foreign;
+
+    #[derive(TypeHash)]
+    pub struct Node {
+        next: Option<Box<Node>>,
+    }
+
+    pub struct Point(i32, i32);
+
+    impl<M: ?Sized> TypeEncoding<M> for Point {
+        const ENCODING: &'static [Piece] = &[
+            Piece::Tag(Tag::TupleStruct),
+            Piece::Str("Point"),
+            Piece::Len(2),
+            Piece::Tag(Tag::Field),
+            Piece::Type(<i32 as TypeEncoding<M>>::ENCODING),
+            Piece::Tag(Tag::Field),
+            Piece::Type(<i32 as TypeEncoding<M>>::ENCODING),
+        ];
+    }
+
+    impl TypeHash for Point {
+        fn shape_within(ancestors: &Ancestors<'_>) -> Shape {
+            Shape::Struct {
+                name: "Point".into(),
+                fields: Fields::Unnamed(vec![
+                    i32::shape_within(ancestors),
+                    i32::shape_within(ancestors),
+                ]),
+            }
+        }
+    }
+
+    pub struct Mismatched;
+
+    impl<M: ?Sized> TypeEncoding<M> for Mismatched {
+        const ENCODING: &'static [Piece] = &[Piece::Tag(Tag::UnitStruct), Piece::Str("Other")];
+    }
+
+    impl TypeHash for Mismatched {
+        fn shape_within(_: &Ancestors<'_>) -> Shape {
+            Shape::Struct {
+                name: "Mismatched".into(),
+                fields: Fields::Unit,
+            }
+        }
+    }
+}
+
+mod v2 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct Point(i32, i32);
+}
@@ -0,0 +1,70 @@
+#![allow(unused)]
+use facade::hashing::TypeHash;
+
+mod facade {
+    pub use ::type_hash as hashing;
+}
+
+#[test]
+fn crate_path() {
+    assert_eq!(v1::Message::type_hash(), v2::Message::type_hash());
+    assert_eq!(v1::Message::TYPE_HASH, v2::Message::type_hash());
+    assert_eq!(
+        <dyn v1::Handler>::type_hash(),
+        <dyn v2::Handler>::type_hash()
+    );
+}
+
+#[test]
+fn crate_path_from_macro_rules() {
+    assert_eq!(v3::Message::type_hash(), v1::Message::type_hash());
+}
+
+mod v1 {
+    #[derive(type_hash::TypeHash)]
+    pub enum Message {
+        LaunchMissiles { destination: String },
+        CancelMissiles,
+    }
+
+    #[type_hash::trait_hash]
+    pub trait Handler {
+        fn handle(&self, message: &Message);
+    }
+}
+
+mod v2 {
+    use crate::facade::hashing;
+
+    // Generated code that refers to `type_hash` would find this instead
+    mod type_hash {}
+
+    #[derive(hashing::TypeHash)]
+    #[type_hash(crate = "crate::facade::hashing")]
+    pub enum Message {
+        LaunchMissiles { destination: String },
+        CancelMissiles,
+    }
+
+    #[hashing::trait_hash(crate = crate::facade::hashing)]
+    pub trait Handler {
+        fn handle(&self, message: &Message);
+    }
+}
+
+macro_rules! message {
+    () => {
+        #[derive($crate::facade::hashing::TypeHash)]
+        #[type_hash(crate = $crate::facade::hashing)]
+        pub enum Message {
+            LaunchMissiles { destination: String },
+            CancelMissiles,
+        }
+    };
+}
+
+mod v3 {
+    mod type_hash {}
+
+    message!();
+}
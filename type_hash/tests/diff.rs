@@ -0,0 +1,270 @@
+#![cfg(feature = "std")]
+#![allow(unused)]
+use type_hash::diff::{diff, ChangeKind};
+use type_hash::shape::Shape;
+use type_hash::TypeHash;
+
+fn changes<Old: TypeHash, New: TypeHash>() -> Vec<String> {
+    diff(&Old::shape(), &New::shape())
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+}
+
+#[test]
+fn same_type_has_no_changes() {
+    assert!(changes::<v1::Wibble, v1::Wibble>().is_empty());
+    assert!(changes::<v1::Node, v2::Node>().is_empty());
+    assert!(changes::<(u8, Vec<String>), (u8, Vec<String>)>().is_empty());
+}
+
+#[test]
+fn changed_field_type_is_reported_at_every_use() {
+    assert_eq!(changes::<v1::Foo, v2::Foo>(), ["Foo.a: i64 -> u64"]);
+    assert_eq!(
+        changes::<v1::Wibble, v2::Wibble>(),
+        [
+            "Wibble::A.0::B discriminant 2 -> 3",
+            "Wibble::B.0.a: i64 -> u64",
+            "Wibble::B.1.a: i64 -> u64",
+            "Wibble::C.foo.a: i64 -> u64",
+        ]
+    );
+}
+
+#[test]
+fn changed_discriminant() {
+    assert_eq!(
+        changes::<v1::Bar, v2::Bar>(),
+        ["Bar::B discriminant 2 -> 3"]
+    );
+    assert_eq!(
+        changes::<v2::Bar, v3::Bar>(),
+        [
+            "Bar::A discriminant 1 -> none",
+            "Bar::B discriminant 3 -> none"
+        ]
+    );
+}
+
+#[test]
+fn removed_and_added_fields() {
+    assert_eq!(
+        changes::<v1::Foo, v3::Foo>(),
+        ["Foo.b removed", "Foo.c added"]
+    );
+    let changes = diff(&v1::Foo::shape(), &v3::Foo::shape());
+    assert_eq!(changes[0].kind, ChangeKind::FieldRemoved(String::shape()));
+    assert_eq!(
+        changes[1].kind,
+        ChangeKind::FieldAdded(<Option<u8>>::shape())
+    );
+}
+
+#[test]
+fn reordered_fields_and_variants() {
+    assert_eq!(
+        changes::<v1::Point, v2::Point>(),
+        ["Point.x moved 0 -> 1", "Point.y moved 1 -> 0"]
+    );
+    assert_eq!(
+        changes::<v1::Shape, v2::Shape>(),
+        [
+            "Shape::Circle moved 0 -> 1",
+            "Shape::Square removed",
+            "Shape::Line moved 2 -> 0",
+            "Shape::Triangle added",
+        ]
+    );
+}
+
+#[test]
+fn changed_kind_of_fields() {
+    assert_eq!(
+        changes::<v1::Kinds, v2::Kinds>(),
+        [
+            "Kinds::A: no fields -> unnamed fields",
+            "Kinds::B: named fields -> no fields",
+            "Kinds::C.1 removed",
+        ]
+    );
+}
+
+#[test]
+fn changes_inside_built_in_types() {
+    assert_eq!(
+        changes::<Vec<Option<v1::Foo>>, Vec<Option<v2::Foo>>>(),
+        ["[_]<0>.a: i64 -> u64"]
+    );
+    assert_eq!(
+        changes::<
+            std::collections::HashMap<u8, [v1::Foo; 2]>,
+            std::collections::HashMap<u16, [v2::Foo; 2]>,
+        >(),
+        ["[key]: u8 -> u16", "[value][_].a: i64 -> u64"]
+    );
+    assert_eq!(
+        changes::<(&[u8], *const str), (&mut [u8], *mut str)>(),
+        [".0: &[u8] -> &mut [u8]", ".1: *const str -> *mut str"]
+    );
+}
+
+#[test]
+fn changed_type_name() {
+    assert_eq!(
+        changes::<v1::Holder, v2::Holder>(),
+        ["Holder.0: Foo -> Other"]
+    );
+    assert_eq!(changes::<v1::Foo, v2::Other>(), ["Foo -> Other"]);
+}
+
+#[test]
+fn changes_in_recursive_types() {
+    assert_eq!(
+        changes::<v1::Tree, v2::Tree>(),
+        ["Tree.children[_]: Tree -> Option<Tree>"]
+    );
+    assert_eq!(changes::<v1::Tree, v3::Tree>(), ["Tree.value: u32 -> u64"]);
+}
+
+mod v1 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct Foo {
+        a: i64,
+        b: String,
+    }
+
+    #[derive(TypeHash)]
+    pub enum Bar {
+        A = 1,
+        B = 2,
+    }
+
+    #[derive(TypeHash)]
+    pub enum Wibble {
+        A(Bar),
+        B(Foo, Foo),
+        C { foo: Foo },
+    }
+
+    #[derive(TypeHash)]
+    pub struct Point {
+        x: f64,
+        y: f64,
+    }
+
+    #[derive(TypeHash)]
+    pub enum Shape {
+        Circle,
+        Square,
+        Line,
+    }
+
+    #[derive(TypeHash)]
+    pub enum Kinds {
+        A,
+        B { x: u8 },
+        C(u8, u8),
+    }
+
+    #[derive(TypeHash)]
+    pub struct Holder(Foo);
+
+    #[derive(TypeHash)]
+    pub struct Node {
+        next: Option<Box<Node>>,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Tree {
+        value: u32,
+        children: Vec<Tree>,
+    }
+}
+
+mod v2 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct Foo {
+        a: u64,
+        b: String,
+    }
+
+    #[derive(TypeHash)]
+    pub enum Bar {
+        A = 1,
+        B = 3,
+    }
+
+    #[derive(TypeHash)]
+    pub enum Wibble {
+        A(Bar),
+        B(Foo, Foo),
+        C { foo: Foo },
+    }
+
+    #[derive(TypeHash)]
+    pub struct Point {
+        y: f64,
+        x: f64,
+    }
+
+    #[derive(TypeHash)]
+    pub enum Shape {
+        Line,
+        Circle,
+        Triangle,
+    }
+
+    #[derive(TypeHash)]
+    pub enum Kinds {
+        A(u8),
+        B,
+        C(u8),
+    }
+
+    #[derive(TypeHash)]
+    pub struct Other {
+        a: i64,
+        b: String,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Holder(Other);
+
+    #[derive(TypeHash)]
+    pub struct Node {
+        next: Option<Box<Node>>,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Tree {
+        value: u32,
+        children: Vec<Option<Tree>>,
+    }
+}
+
+mod v3 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct Foo {
+        a: i64,
+        c: Option<u8>,
+    }
+
+    #[derive(TypeHash)]
+    pub enum Bar {
+        A,
+        B,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Tree {
+        value: u64,
+        children: Vec<Tree>,
+    }
+}
@@ -0,0 +1,326 @@
+#![allow(unused)]
+use std::hash::Hasher;
+use type_hash::encoding::Tag;
+use type_hash::TypeHash;
+
+/// Records the bytes that are written to it, and panics if anything is written with a
+/// native byte order or width.
+#[derive(Default)]
+struct Recorder(Vec<u8>);
+
+impl Hasher for Recorder {
+    fn finish(&self) -> u64 {
+        unreachable!("the encoding tests never call finish")
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn write_u16(&mut self, _: u16) {
+        panic!("write_u16 is platform-dependent");
+    }
+
+    fn write_u32(&mut self, _: u32) {
+        panic!("write_u32 is platform-dependent");
+    }
+
+    fn write_u64(&mut self, _: u64) {
+        panic!("write_u64 is platform-dependent");
+    }
+
+    fn write_u128(&mut self, _: u128) {
+        panic!("write_u128 is platform-dependent");
+    }
+
+    fn write_usize(&mut self, _: usize) {
+        panic!("write_usize is platform-dependent");
+    }
+
+    fn write_i16(&mut self, _: i16) {
+        panic!("write_i16 is platform-dependent");
+    }
+
+    fn write_i32(&mut self, _: i32) {
+        panic!("write_i32 is platform-dependent");
+    }
+
+    fn write_i64(&mut self, _: i64) {
+        panic!("write_i64 is platform-dependent");
+    }
+
+    fn write_i128(&mut self, _: i128) {
+        panic!("write_i128 is platform-dependent");
+    }
+
+    fn write_isize(&mut self, _: isize) {
+        panic!("write_isize is platform-dependent");
+    }
+}
+
+fn recorded<T: TypeHash + ?Sized>() -> Vec<u8> {
+    let mut recorder = Recorder::default();
+    T::write_hash(&mut recorder);
+    recorder.0
+}
+
+fn tag(tag: Tag) -> Vec<u8> {
+    vec![tag as u8]
+}
+
+fn len(len: u64) -> Vec<u8> {
+    len.to_le_bytes().to_vec()
+}
+
+fn name(name: &str) -> Vec<u8> {
+    [len(name.len() as u64), name.as_bytes().to_vec()].concat()
+}
+
+fn primitive(ty: &str) -> Vec<u8> {
+    [tag(Tag::Primitive), name(ty)].concat()
+}
+
+#[test]
+fn array_is_written_with_little_endian_length() {
+    assert_eq!(
+        recorded::<[u16; 3]>(),
+        [tag(Tag::Array), len(3), primitive("u16")].concat()
+    );
+}
+
+#[test]
+fn struct_is_written_with_little_endian_lengths() {
+    assert_eq!(
+        recorded::<v1::S>(),
+        [
+            tag(Tag::Struct),
+            name("S"),
+            len(1),
+            tag(Tag::Field),
+            name("ab"),
+            primitive("u8"),
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn const_arguments_are_written_before_the_type() {
+    assert_eq!(
+        recorded::<v1::Const<true, 'x', -2>>(),
+        [
+            tag(Tag::ConstArgs),
+            len(3),
+            tag(Tag::Bool),
+            vec![1],
+            tag(Tag::Char),
+            120u32.to_le_bytes().to_vec(),
+            tag(Tag::Int),
+            (-2i128).to_le_bytes().to_vec(),
+            tag(Tag::UnitStruct),
+            name("Const"),
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn union_is_written_with_its_own_tag() {
+    assert_eq!(
+        recorded::<v1::U>(),
+        [
+            tag(Tag::Union),
+            name("U"),
+            len(1),
+            tag(Tag::Field),
+            name("ab"),
+            primitive("u8"),
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn discriminants_are_written_as_little_endian_i128() {
+    assert_eq!(
+        recorded::<v1::Discriminants>(),
+        [
+            tag(Tag::Enum),
+            name("Discriminants"),
+            len(2),
+            tag(Tag::Variant),
+            len(0),
+            name("Min"),
+            tag(Tag::Discriminant),
+            (-1i128).to_le_bytes().to_vec(),
+            tag(Tag::UnitStruct),
+            tag(Tag::Variant),
+            len(1),
+            name("Max"),
+            tag(Tag::Discriminant),
+            (i32::MAX as i128).to_le_bytes().to_vec(),
+            tag(Tag::UnitStruct),
+        ]
+        .concat()
+    );
+    assert_eq!(
+        recorded::<v1::Unsigned>(),
+        [
+            tag(Tag::Enum),
+            name("Unsigned"),
+            len(1),
+            tag(Tag::Variant),
+            len(0),
+            name("Max"),
+            tag(Tag::Discriminant),
+            (u64::MAX as i128).to_le_bytes().to_vec(),
+            tag(Tag::UnitStruct),
+        ]
+        .concat()
+    );
+    assert_eq!(
+        recorded::<v1::Wide>(),
+        [
+            tag(Tag::Enum),
+            name("Wide"),
+            len(1),
+            tag(Tag::Variant),
+            len(0),
+            name("Max"),
+            tag(Tag::Discriminant),
+            i128::MAX.to_le_bytes().to_vec(),
+            tag(Tag::UnitStruct),
+        ]
+        .concat()
+    );
+    assert_eq!(v1::Wide::TYPE_HASH, v1::Wide::type_hash());
+}
+
+#[test]
+fn back_reference_is_written_with_little_endian_depth() {
+    assert_eq!(
+        recorded::<v1::Node>(),
+        [
+            tag(Tag::TupleStruct),
+            name("Node"),
+            len(1),
+            tag(Tag::Field),
+            tag(Tag::Sequence),
+            name("std::vec::Vec"),
+            tag(Tag::BackRef),
+            len(0),
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn struct_and_enum_with_same_name_and_fields_have_different_hashes() {
+    assert_ne!(v1::A::type_hash(), v2::A::type_hash());
+}
+
+#[test]
+fn structs_of_different_kinds_have_different_hashes() {
+    assert_ne!(v1::Unit::type_hash(), v2::Unit::type_hash());
+    assert_ne!(v1::Unit::type_hash(), v3::Unit::type_hash());
+    assert_ne!(v2::Unit::type_hash(), v3::Unit::type_hash());
+}
+
+#[test]
+fn adjacent_names_do_not_run_together() {
+    assert_ne!(v1::S::type_hash(), v1::Sa::type_hash());
+    assert_ne!(v1::Tu::type_hash(), v1::T::type_hash());
+}
+
+#[test]
+fn tuples_of_different_lengths_have_different_hashes() {
+    assert_ne!(
+        <((u8, u8), u8)>::type_hash(),
+        <((u8,), u8, u8)>::type_hash()
+    );
+    assert_ne!(
+        <(Vec<u8>, u8)>::type_hash(),
+        <(Vec<(u8, u8)>,)>::type_hash()
+    );
+}
+
+#[test]
+fn arrays_and_tuples_have_different_hashes() {
+    assert_ne!(<[u8; 2]>::type_hash(), <(u8, u8)>::type_hash());
+    assert_ne!(<[u8; 1]>::type_hash(), <&[u8]>::type_hash());
+}
+
+mod v1 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct A(u32);
+
+    #[derive(TypeHash)]
+    pub struct Unit;
+
+    #[derive(TypeHash)]
+    pub struct S {
+        ab: u8,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Sa {
+        b: u8,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Tu(u8);
+
+    #[derive(TypeHash)]
+    pub struct Const<const X: bool, const Y: char, const Z: i64>;
+
+    #[derive(TypeHash)]
+    pub union U {
+        ab: u8,
+    }
+
+    #[derive(TypeHash)]
+    pub struct T(u8, u8);
+
+    #[derive(TypeHash)]
+    pub enum Discriminants {
+        Min = -1,
+        Max = i32::MAX as isize,
+    }
+
+    #[derive(TypeHash)]
+    #[repr(u64)]
+    pub enum Unsigned {
+        Max = 0xffff_ffff_ffff_ffff,
+    }
+
+    // The largest `u128` discriminant that can be hashed
+    #[derive(TypeHash)]
+    #[repr(u128)]
+    pub enum Wide {
+        Max = i128::MAX as u128,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Node(Vec<Node>);
+}
+
+mod v2 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub enum A {
+        X(u32),
+    }
+
+    #[derive(TypeHash)]
+    pub struct Unit {}
+}
+
+mod v3 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct Unit();
+}
@@ -0,0 +1,108 @@
+#![allow(unused)]
+use type_hash::TypeHash;
+
+#[test]
+fn same_enum_has_same_hash() {
+    assert_eq!(v1::Added::type_hash(), v1::Added::type_hash());
+    assert_eq!(v1::Renamed::type_hash(), v1::Renamed::type_hash());
+}
+
+#[test]
+fn enum_with_added_variant_has_different_hash() {
+    assert_ne!(v1::Added::type_hash(), v2::Added::type_hash());
+}
+
+#[test]
+fn enum_with_renamed_variant_has_different_hash() {
+    assert_ne!(v1::Renamed::type_hash(), v2::Renamed::type_hash());
+}
+
+#[test]
+fn enum_with_reordered_variants_has_different_hash() {
+    assert_ne!(v1::Reordered::type_hash(), v2::Reordered::type_hash());
+}
+
+#[test]
+fn enum_with_different_variant_kind_has_different_hash() {
+    assert_ne!(v1::UnitToTuple::type_hash(), v2::UnitToTuple::type_hash());
+    assert_ne!(
+        v1::TupleToStruct::type_hash(),
+        v2::TupleToStruct::type_hash()
+    );
+}
+
+#[test]
+fn empty_enum_has_different_hash_to_enum_with_variants() {
+    assert_ne!(v1::Empty::type_hash(), v2::Empty::type_hash());
+}
+
+mod v1 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub enum Added {
+        A,
+        B,
+    }
+
+    #[derive(TypeHash)]
+    pub enum Renamed {
+        X(u32),
+    }
+
+    #[derive(TypeHash)]
+    pub enum Reordered {
+        A(u32),
+        B(u32),
+    }
+
+    #[derive(TypeHash)]
+    pub enum UnitToTuple {
+        A,
+    }
+
+    #[derive(TypeHash)]
+    pub enum TupleToStruct {
+        A(u32),
+    }
+
+    #[derive(TypeHash)]
+    pub enum Empty {}
+}
+
+mod v2 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub enum Added {
+        A,
+        B,
+        C,
+    }
+
+    #[derive(TypeHash)]
+    pub enum Renamed {
+        Y(u32),
+    }
+
+    #[derive(TypeHash)]
+    pub enum Reordered {
+        B(u32),
+        A(u32),
+    }
+
+    #[derive(TypeHash)]
+    pub enum UnitToTuple {
+        A(),
+    }
+
+    #[derive(TypeHash)]
+    pub enum TupleToStruct {
+        A { x: u32 },
+    }
+
+    #[derive(TypeHash)]
+    pub enum Empty {
+        A,
+    }
+}
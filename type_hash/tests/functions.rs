@@ -0,0 +1,153 @@
+#![allow(unused)]
+use std::marker::PhantomData;
+use type_hash::diff::diff;
+use type_hash::shape::Shape;
+use type_hash::TypeHash;
+
+#[test]
+fn function_pointer_shape() {
+    assert_eq!(
+        <fn(u32) -> bool>::shape(),
+        Shape::Function {
+            abi: "Rust".into(),
+            is_unsafe: false,
+            args: vec![u32::shape()],
+            output: Box::new(bool::shape()),
+        }
+    );
+    assert_eq!(
+        <unsafe extern "C" fn(*const u8)>::shape(),
+        Shape::Function {
+            abi: "C".into(),
+            is_unsafe: true,
+            args: vec![<*const u8>::shape()],
+            output: Box::new(<()>::shape()),
+        }
+    );
+}
+
+#[test]
+fn function_pointers_are_displayed_as_types() {
+    assert_eq!(<fn()>::shape().to_string(), "fn()");
+    assert_eq!(
+        <fn(u32, String) -> bool>::shape().to_string(),
+        "fn(u32, String) -> bool"
+    );
+    assert_eq!(
+        <unsafe extern "system" fn(*mut u8) -> i32>::shape().to_string(),
+        "unsafe extern \"system\" fn(*mut u8) -> i32"
+    );
+}
+
+#[test]
+fn abi_and_unsafe_are_part_of_the_hash() {
+    let hashes = [
+        <fn(u8)>::type_hash(),
+        <unsafe fn(u8)>::type_hash(),
+        <extern "C" fn(u8)>::type_hash(),
+        <unsafe extern "C" fn(u8)>::type_hash(),
+        <extern "system" fn(u8)>::type_hash(),
+        <unsafe extern "system" fn(u8)>::type_hash(),
+    ];
+    for (i, a) in hashes.iter().enumerate() {
+        for b in &hashes[i + 1..] {
+            assert_ne!(a, b);
+        }
+    }
+}
+
+#[test]
+fn arguments_and_output_are_part_of_the_hash() {
+    assert_ne!(<fn(u8)>::type_hash(), <fn(u16)>::type_hash());
+    assert_ne!(<fn(u8)>::type_hash(), <fn(u8, u8)>::type_hash());
+    assert_ne!(<fn(u8)>::type_hash(), <fn(u8) -> u8>::type_hash());
+    assert_ne!(<fn((u8, u8))>::type_hash(), <fn(u8, u8)>::type_hash());
+    assert_ne!(<fn() -> u8>::type_hash(), <fn(u8)>::type_hash());
+}
+
+#[test]
+fn all_arities() {
+    assert_eq!(
+        <fn(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8>::TYPE_HASH,
+        <fn(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8) -> u8>::type_hash()
+    );
+}
+
+#[test]
+fn derived_types_with_callbacks() {
+    assert_eq!(v1::VTable::TYPE_HASH, v1::VTable::type_hash());
+    assert_ne!(v1::VTable::type_hash(), v2::VTable::type_hash());
+    assert_ne!(
+        v1::Invariant::<u8>::type_hash(),
+        v1::Invariant::<u16>::type_hash()
+    );
+}
+
+#[test]
+fn callbacks_with_elided_lifetimes() {
+    assert_eq!(v1::Callbacks::TYPE_HASH, v1::Callbacks::type_hash());
+    assert_eq!(v1::Callbacks::type_hash(), v2::Callbacks::type_hash());
+    assert_eq!(
+        v1::Callbacks::shape().to_string(),
+        v2::Callbacks::shape().to_string()
+    );
+}
+
+#[test]
+fn diff_of_function_pointers() {
+    let changes: Vec<_> = diff(&v1::VTable::shape(), &v2::VTable::shape())
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            "VTable.read(1): usize -> u64",
+            "VTable.close(output): () -> i32",
+        ]
+    );
+}
+
+mod v1 {
+    use std::marker::PhantomData;
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    #[repr(C)]
+    pub struct VTable {
+        read: unsafe extern "C" fn(*mut u8, usize) -> isize,
+        close: extern "C" fn(*mut u8),
+    }
+
+    #[derive(TypeHash)]
+    pub struct Callbacks<'a> {
+        on_data: fn(&[u8]),
+        parse: for<'b> fn(&'b str) -> &'b str,
+        handler: Box<dyn Fn(&str) -> bool + Send + 'a>,
+        on_error: Option<extern "C" fn(*const u8, &mut Vec<u8>)>,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Invariant<T> {
+        marker: PhantomData<fn(T) -> T>,
+    }
+}
+
+mod v2 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    #[repr(C)]
+    pub struct VTable {
+        read: unsafe extern "C" fn(*mut u8, u64) -> isize,
+        close: extern "C" fn(*mut u8) -> i32,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Callbacks {
+        on_data: fn(&'static [u8]),
+        parse: fn(&'static str) -> &'static str,
+        handler: Box<dyn Fn(&'static str) -> bool + Send>,
+        on_error: Option<extern "C" fn(*const u8, &'static mut Vec<u8>)>,
+    }
+}
@@ -0,0 +1,182 @@
+#![cfg(feature = "std")]
+#![allow(unused)]
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasherDefault, Hasher};
+use type_hash::diff::diff;
+use type_hash::shape::Shape;
+use type_hash::{TypeHash, WithHasher};
+
+#[test]
+fn hasher_is_not_part_of_the_hash() {
+    assert_eq!(
+        <HashMap<u8, String, BuildHasherDefault<fnv::FnvHasher>>>::type_hash(),
+        <HashMap<u8, String>>::type_hash()
+    );
+    assert_eq!(
+        <HashSet<u8, BuildHasherDefault<fnv::FnvHasher>>>::type_hash(),
+        <HashSet<u8>>::type_hash()
+    );
+    assert_eq!(v1::Message::type_hash(), v2::Message::type_hash());
+    assert_eq!(v1::Message::TYPE_HASH, v2::Message::TYPE_HASH);
+}
+
+#[test]
+fn hasher_is_part_of_the_hash_when_chosen() {
+    assert_ne!(v3::Message::type_hash(), v1::Message::type_hash());
+    assert_ne!(v3::Message::type_hash(), v4::Message::type_hash());
+    assert_eq!(v3::Message::TYPE_HASH, v3::Message::type_hash());
+    assert_eq!(v4::Message::TYPE_HASH, v4::Message::type_hash());
+}
+
+#[test]
+fn collections_with_hashers() {
+    assert_eq!(
+        <WithHasher<HashMap<u8, String, BuildHasherDefault<Hash64>>>>::shape(),
+        Shape::Map {
+            name: "std::collections::HashMap".into(),
+            key: Box::new(u8::shape()),
+            value: Box::new(String::shape()),
+            hasher: Some(Box::new(<BuildHasherDefault<Hash64>>::shape())),
+        }
+    );
+    assert_eq!(
+        <WithHasher<HashSet<u8, BuildHasherDefault<Hash64>>>>::shape().to_string(),
+        "HashSet<u8, BuildHasherDefault<Hash64>>"
+    );
+}
+
+#[test]
+fn default_hasher_can_be_part_of_the_hash() {
+    assert_ne!(v5::Message::type_hash(), v3::Message::type_hash());
+    assert_ne!(v5::Message::type_hash(), v1::Message::type_hash());
+    assert_eq!(v5::Message::TYPE_HASH, v5::Message::type_hash());
+    assert_eq!(
+        <std::collections::hash_map::DefaultHasher>::shape().to_string(),
+        "DefaultHasher"
+    );
+}
+
+#[test]
+fn diff_of_hashers() {
+    let changes: Vec<_> = diff(&v5::Message::shape(), &v3::Message::shape())
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            "Message.ids hasher RandomState -> BuildHasherDefault<Hash64>",
+            "Message.names hasher RandomState -> BuildHasherDefault<Hash64>",
+        ]
+    );
+    let changes: Vec<_> = diff(&v1::Message::shape(), &v5::Message::shape())
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            "Message.ids hasher none -> RandomState",
+            "Message.names hasher none -> RandomState",
+        ]
+    );
+}
+
+#[test]
+fn hashers_are_compatible() {
+    use type_hash::compat::{check, Compatibility, Format};
+    let report = check(&v5::Message::shape(), &v3::Message::shape(), Format::Json);
+    assert_eq!(report.compatibility, Compatibility::Compatible);
+    let reasons: Vec<_> = report.reasons.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        reasons,
+        [
+            "Message.ids: hasher RandomState -> BuildHasherDefault<Hash64>: the hasher doesn't change how the collection is written (Compatible)",
+            "Message.names: hasher RandomState -> BuildHasherDefault<Hash64>: the hasher doesn't change how the collection is written (Compatible)",
+        ]
+    );
+}
+
+#[derive(TypeHash, Default)]
+pub struct Hash64(u64);
+
+impl Hasher for Hash64 {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = self.0.rotate_left(8) ^ u64::from(*byte);
+        }
+    }
+}
+
+#[derive(TypeHash, Default)]
+pub struct Hash32(u32);
+
+mod v1 {
+    use std::collections::{HashMap, HashSet};
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct Message {
+        ids: HashSet<u64>,
+        names: HashMap<u64, String>,
+    }
+}
+
+mod v2 {
+    use std::collections::{HashMap, HashSet};
+    use std::hash::BuildHasherDefault;
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct Message {
+        ids: HashSet<u64, BuildHasherDefault<fnv::FnvHasher>>,
+        names: HashMap<u64, String, BuildHasherDefault<fnv::FnvHasher>>,
+    }
+}
+
+mod v3 {
+    use super::Hash64;
+    use std::collections::{HashMap, HashSet};
+    use std::hash::BuildHasherDefault;
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct Message {
+        #[type_hash(hasher)]
+        ids: HashSet<u64, BuildHasherDefault<Hash64>>,
+        #[type_hash(hasher)]
+        names: HashMap<u64, String, BuildHasherDefault<Hash64>>,
+    }
+}
+
+mod v4 {
+    use super::Hash32;
+    use std::collections::{HashMap, HashSet};
+    use std::hash::BuildHasherDefault;
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct Message {
+        #[type_hash(hasher)]
+        ids: HashSet<u64, BuildHasherDefault<Hash32>>,
+        #[type_hash(hasher)]
+        names: HashMap<u64, String, BuildHasherDefault<Hash32>>,
+    }
+}
+
+mod v5 {
+    use std::collections::{HashMap, HashSet};
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct Message {
+        #[type_hash(hasher)]
+        ids: HashSet<u64>,
+        #[type_hash(hasher)]
+        names: HashMap<u64, String>,
+    }
+}
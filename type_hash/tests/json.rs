@@ -0,0 +1,138 @@
+#![cfg(feature = "serde")]
+#![allow(unused)]
+use std::collections::HashMap;
+use type_hash::json::{self, Error};
+use type_hash::TypeHash;
+
+#[test]
+fn document_format() {
+    assert_eq!(
+        json::to_string(&v1::Point::shape()),
+        r#"{
+  "version": 1,
+  "shape": {
+    "struct": {
+      "name": "Point",
+      "fields": {
+        "named": [
+          {
+            "name": "x",
+            "shape": {
+              "primitive": "i32"
+            }
+          },
+          {
+            "name": "y",
+            "shape": {
+              "opaque": {
+                "name": "std::option::Option",
+                "args": [
+                  {
+                    "array": {
+                      "len": 2,
+                      "element": {
+                        "primitive": "u8"
+                      }
+                    }
+                  }
+                ]
+              }
+            }
+          }
+        ]
+      }
+    }
+  }
+}"#
+    );
+}
+
+fn round_trip<T: TypeHash + ?Sized>() {
+    let shape = T::shape();
+    let stored = json::to_string(&shape);
+    assert_eq!(json::from_str(&stored).unwrap(), shape);
+    assert_eq!(json::type_hash(&stored).unwrap(), T::type_hash());
+}
+
+#[test]
+fn shapes_round_trip() {
+    round_trip::<v1::Point>();
+    round_trip::<v1::Message>();
+    round_trip::<v1::Discriminants>();
+    round_trip::<v1::Node>();
+    round_trip::<v1::Foreign>();
+    round_trip::<(u8, &mut [bool], *const str)>();
+    round_trip::<HashMap<String, Vec<v1::Node>>>();
+    round_trip::<()>();
+    round_trip::<v1::Const<{ i128::MIN }, false, '\u{10ffff}'>>();
+}
+
+#[test]
+fn hash_can_be_checked_from_stored_document() {
+    let stored = r#"{
+        "version": 1,
+        "shape": {"struct": {"name": "Unit", "fields": "unit"}}
+    }"#;
+    assert_eq!(json::type_hash(stored).unwrap(), v1::Unit::type_hash());
+}
+
+#[test]
+fn newer_version_is_an_error() {
+    let stored = r#"{"version": 2, "shape": {"something": "new"}}"#;
+    match json::from_str(stored) {
+        Err(Error::UnsupportedVersion(2)) => {}
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn invalid_document_is_an_error() {
+    assert!(matches!(json::from_str("{}"), Err(Error::Json(_))));
+    assert!(matches!(
+        json::from_str(r#"{"version": 1, "shape": {"primitive": 1}}"#),
+        Err(Error::Json(_))
+    ));
+}
+
+mod v1 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct Point {
+        x: i32,
+        y: Option<[u8; 2]>,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Unit;
+
+    #[derive(TypeHash)]
+    pub enum Message {
+        LaunchMissiles { destination: String },
+        Wait(std::time::Duration, u8),
+        CancelMissiles,
+    }
+
+    #[derive(TypeHash)]
+    #[repr(i128)]
+    pub enum Discriminants {
+        Min = i128::MIN,
+        Max = u64::MAX as i128,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Node {
+        children: Vec<Node>,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Foreign {
+        #[type_hash(foreign_type)]
+        value: Vec<Unknown>,
+    }
+
+    pub struct Unknown;
+
+    #[derive(TypeHash)]
+    pub struct Const<const A: i128, const B: bool, const C: char>;
+}
@@ -0,0 +1,135 @@
+#![cfg(feature = "serde")]
+#![allow(unused)]
+use std::fs;
+use std::path::PathBuf;
+use type_hash::lock::{Drift, Error, Lockfile};
+use type_hash::TypeHash;
+
+fn temp_lockfile(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("type_hash_lock_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir.join("type_hash.lock")
+}
+
+#[test]
+fn lockfile_format() {
+    let lockfile = Lockfile::new().with_name::<v1::Unit>("Unit");
+    assert_eq!(
+        lockfile.to_json(),
+        r#"{
+  "version": 1,
+  "types": {
+    "Unit": {
+      "hash": 17179224262049340949,
+      "shape": {
+        "struct": {
+          "name": "Unit",
+          "fields": "unit"
+        }
+      }
+    }
+  }
+}
+"#
+    );
+    assert_eq!(Lockfile::from_json(&lockfile.to_json()).unwrap(), lockfile);
+}
+
+#[test]
+fn types_are_keyed_by_name() {
+    let lockfile = Lockfile::new()
+        .with_name::<v1::Message>("messages::Message")
+        .with_name::<u8>("Byte");
+    assert_eq!(
+        lockfile.types.keys().collect::<Vec<_>>(),
+        ["Byte", "messages::Message"]
+    );
+    assert_eq!(
+        lockfile.types["messages::Message"].hash,
+        v1::Message::type_hash()
+    );
+}
+
+#[test]
+fn written_lockfile_is_verified() {
+    let path = temp_lockfile("written");
+    let lockfile = Lockfile::new()
+        .with_name::<v1::Message>("Message")
+        .with_name::<v1::Unit>("Unit");
+    lockfile.write(&path).unwrap();
+    lockfile.verify(&path).unwrap();
+    assert_eq!(Lockfile::read(&path).unwrap(), lockfile);
+}
+
+#[test]
+fn drift_is_an_error() {
+    let path = temp_lockfile("drift");
+    Lockfile::new()
+        .with_name::<v1::Message>("Message")
+        .with_name::<v1::Unit>("Unit")
+        .write(&path)
+        .unwrap();
+    let current = Lockfile::new()
+        .with_name::<v2::Message>("Message")
+        .with_name::<u8>("Byte");
+    let drift = match current.verify(&path) {
+        Err(Error::Drift(drift)) => drift,
+        other => panic!("unexpected result: {:?}", other),
+    };
+    assert_eq!(drift.len(), 3);
+    assert_eq!(drift[0], Drift::Removed("Unit".into()));
+    assert_eq!(drift[1], Drift::Added("Byte".into()));
+    assert_eq!(
+        Error::Drift(drift).to_string(),
+        format!(
+            "the types differ from the lockfile:
+Unit: in the lockfile, but no longer included
+Byte: not in the lockfile
+Message: hash changed 0x8a0eeb94bb00c95e -> {:#018x}
+  Message::LaunchMissiles.destination: String -> u64
+  Message::CancelMissiles removed
+set TYPE_HASH_UPDATE=1 to update the lockfile",
+            v2::Message::type_hash()
+        )
+    );
+}
+
+#[test]
+fn missing_lockfile_is_an_error() {
+    let path = temp_lockfile("missing");
+    assert!(matches!(
+        Lockfile::new().with_name::<u8>("Byte").verify(&path),
+        Err(Error::Io(_))
+    ));
+}
+
+#[test]
+fn newer_version_is_an_error() {
+    assert!(matches!(
+        Lockfile::from_json(r#"{"version": 2, "types": {}}"#),
+        Err(type_hash::json::Error::UnsupportedVersion(2))
+    ));
+}
+
+mod v1 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub enum Message {
+        LaunchMissiles { destination: String },
+        CancelMissiles,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Unit;
+}
+
+mod v2 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub enum Message {
+        LaunchMissiles { destination: u64 },
+    }
+}
@@ -0,0 +1,116 @@
+#![allow(unused)]
+use type_hash::diff::diff;
+use type_hash::shape::Shape;
+use type_hash::TypeHash;
+
+#[test]
+fn renamed_types_keep_their_hash() {
+    assert_eq!(v1::Message::type_hash(), v2::WireMessage::type_hash());
+    assert_eq!(v1::Message::TYPE_HASH, v2::WireMessage::TYPE_HASH);
+    assert_eq!(v1::Point::type_hash(), v2::WirePoint::type_hash());
+    assert_eq!(v1::Point::TYPE_HASH, v2::WirePoint::TYPE_HASH);
+    assert_eq!(v1::Bits::type_hash(), v2::WireBits::type_hash());
+    assert_eq!(
+        <dyn v1::Handler>::type_hash(),
+        <dyn v2::WireHandler>::type_hash()
+    );
+}
+
+#[test]
+fn name_is_used_in_the_shape() {
+    assert_eq!(v2::WireMessage::shape(), v1::Message::shape());
+    assert_eq!(v2::WirePoint::shape().to_string(), "Point");
+    assert_eq!(
+        <dyn v2::WireHandler + Send>::shape().to_string(),
+        "dyn Handler + Send"
+    );
+    assert!(diff(&v1::Message::shape(), &v2::WireMessage::shape()).is_empty());
+}
+
+#[test]
+fn name_changes_the_hash() {
+    assert_ne!(v1::Point::type_hash(), v3::Point::type_hash());
+    let changes: Vec<_> = diff(&v1::Point::shape(), &v3::Point::shape())
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(changes, ["Point -> Position"]);
+}
+
+#[cfg(feature = "registry")]
+#[test]
+fn registered_by_name() {
+    let registered = type_hash::registry::by_hash(v2::WirePoint::type_hash())
+        .next()
+        .unwrap();
+    assert_eq!(registered.name(), "names::v2::Point");
+}
+
+mod v1 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub enum Message {
+        LaunchMissiles { destination: String },
+        CancelMissiles,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    #[derive(TypeHash)]
+    pub union Bits {
+        int: u32,
+        float: f32,
+    }
+
+    #[type_hash::trait_hash]
+    pub trait Handler {
+        fn handle(&self, message: &Message);
+    }
+}
+
+mod v2 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    #[type_hash(name = "Message")]
+    pub enum WireMessage {
+        LaunchMissiles { destination: String },
+        CancelMissiles,
+    }
+
+    #[derive(TypeHash)]
+    #[cfg_attr(feature = "registry", type_hash(register, name = "Point"))]
+    #[cfg_attr(not(feature = "registry"), type_hash(name = "Point"))]
+    pub struct WirePoint {
+        x: i32,
+        y: i32,
+    }
+
+    #[derive(TypeHash)]
+    #[type_hash(name = "Bits")]
+    pub union WireBits {
+        int: u32,
+        float: f32,
+    }
+
+    #[type_hash::trait_hash(name = "Handler")]
+    pub trait WireHandler {
+        fn handle(&self, message: &WireMessage);
+    }
+}
+
+mod v3 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    #[type_hash(name = "Position")]
+    pub struct Point {
+        x: i32,
+        y: i32,
+    }
+}
@@ -0,0 +1,102 @@
+#![allow(unused)]
+use type_hash::TypeHash;
+
+#[test]
+fn directly_recursive_type_can_be_hashed() {
+    assert_eq!(v1::Node::type_hash(), v1::Node::type_hash());
+    assert_eq!(v1::Tree::<u32>::type_hash(), v1::Tree::<u32>::type_hash());
+}
+
+#[test]
+fn recursive_types_with_different_generic_args_give_different_hashes() {
+    assert_ne!(v1::Tree::<u32>::type_hash(), v1::Tree::<u64>::type_hash());
+}
+
+#[test]
+fn mutually_recursive_types_can_be_hashed() {
+    assert_eq!(v1::Expr::type_hash(), v1::Expr::type_hash());
+    assert_eq!(v1::Stmt::type_hash(), v1::Stmt::type_hash());
+    assert_ne!(v1::Expr::type_hash(), v1::Stmt::type_hash());
+}
+
+#[test]
+fn different_recursive_type_with_same_name_and_structure_has_same_hash() {
+    assert_eq!(v1::Node::type_hash(), v2::Node::type_hash());
+}
+
+#[test]
+fn recursive_type_with_different_structure_has_different_hash() {
+    assert_ne!(v1::Tree::<u32>::type_hash(), v2::Tree::<u32>::type_hash());
+    assert_ne!(v1::Stmt::type_hash(), v2::Stmt::type_hash());
+}
+
+#[test]
+fn recursive_type_hashes_differently_to_type_with_same_name() {
+    assert_ne!(v1::Node::type_hash(), v3::Node::type_hash());
+}
+
+mod v1 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct Node {
+        value: u32,
+        next: Option<Box<Node>>,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Tree<T> {
+        value: T,
+        children: Vec<Tree<T>>,
+    }
+
+    #[derive(TypeHash)]
+    pub enum Expr {
+        Literal(i64),
+        Block(Vec<Stmt>),
+    }
+
+    #[derive(TypeHash)]
+    pub enum Stmt {
+        Expr(Expr),
+        Let { name: String, value: Expr },
+    }
+}
+
+mod v2 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct Node {
+        value: u32,
+        next: Option<Box<Node>>,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Tree<T> {
+        value: T,
+        children: Vec<Box<Tree<T>>>,
+    }
+
+    #[derive(TypeHash)]
+    pub enum Expr {
+        Literal(i64),
+        Block(Vec<Stmt>),
+    }
+
+    #[derive(TypeHash)]
+    pub enum Stmt {
+        Expr(Expr),
+        Let { name: String, value: Box<Expr> },
+    }
+}
+
+mod v3 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct Node {
+        value: u32,
+        next: Option<Box<super::v1::Node>>,
+    }
+}
@@ -0,0 +1,94 @@
+#![cfg(feature = "registry")]
+#![allow(unused)]
+use type_hash::registry;
+use type_hash::TypeHash;
+
+#[test]
+fn registered_types_are_found_by_name() {
+    let ping = registry::by_name("registry::v1::Ping").unwrap();
+    assert_eq!(ping.name(), "registry::v1::Ping");
+    assert_eq!(ping.type_name(), "registry::v1::Ping");
+    assert_eq!(ping.shape(), v1::Ping::shape());
+    assert_eq!(ping.type_hash(), v1::Ping::type_hash());
+    assert!(registry::by_name("registry::v1::Pong").is_some());
+    assert!(registry::by_name("Ping").is_none());
+}
+
+#[test]
+fn unregistered_types_are_not_found() {
+    assert!(registry::by_name("registry::v1::Unregistered").is_none());
+    assert!(registry::by_hash(v1::Unregistered::type_hash())
+        .next()
+        .is_none());
+}
+
+#[test]
+fn registered_types_are_found_by_hash() {
+    let found: Vec<_> = registry::by_hash(v1::Pong::type_hash())
+        .map(|registered| registered.name())
+        .collect();
+    assert_eq!(found, ["registry::v1::Pong"]);
+}
+
+#[test]
+fn all_registered_types() {
+    let mut names: Vec<_> = registry::all()
+        .map(|registered| registered.name())
+        .collect();
+    names.sort_unstable();
+    assert_eq!(
+        names,
+        [
+            "registry::v1::Ping",
+            "registry::v1::Pong",
+            "registry::v2::Ping"
+        ]
+    );
+}
+
+#[test]
+fn registered_types_have_distinct_hashes() {
+    assert!(registry::collisions().is_empty());
+    assert!(registry::duplicate_names().is_empty());
+    assert!(registry::check_collisions().is_ok());
+    registry::assert_no_collisions();
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn lockfile_of_registered_types() {
+    let lockfile = type_hash::lock::Lockfile::new().with_registered();
+    assert_eq!(lockfile.types.len(), 3);
+    assert_eq!(
+        lockfile.types["registry::v2::Ping"].hash,
+        v2::Ping::type_hash()
+    );
+}
+
+mod v1 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    #[type_hash(register)]
+    pub struct Ping;
+
+    #[derive(TypeHash)]
+    #[type_hash(register)]
+    pub enum Pong {
+        Ok,
+        Err(String),
+    }
+
+    #[derive(TypeHash)]
+    pub struct Unregistered(u8);
+}
+
+mod v2 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    #[type_hash(register)]
+    pub struct Ping {
+        id: u64,
+    }
+}
@@ -0,0 +1,194 @@
+#![cfg(feature = "std")]
+#![allow(unused)]
+use std::collections::HashMap;
+use type_hash::shape::{Field, Fields, Shape, Variant};
+use type_hash::TypeHash;
+
+fn primitive(name: &str) -> Shape {
+    Shape::Primitive(name.to_owned())
+}
+
+fn opaque(name: &str, args: Vec<Shape>) -> Shape {
+    Shape::Opaque {
+        name: name.to_owned(),
+        args,
+    }
+}
+
+#[test]
+fn shape_of_struct() {
+    assert_eq!(
+        v1::Point::shape(),
+        Shape::Struct {
+            name: "Point".to_owned(),
+            fields: Fields::Named(vec![
+                Field {
+                    name: "x".to_owned(),
+                    shape: primitive("i32"),
+                },
+                Field {
+                    name: "label".to_owned(),
+                    shape: opaque("std::option::Option", vec![primitive("char")]),
+                },
+            ]),
+        }
+    );
+}
+
+#[test]
+fn shape_of_enum() {
+    assert_eq!(
+        v1::Message::shape(),
+        Shape::Enum {
+            name: "Message".to_owned(),
+            variants: vec![
+                Variant {
+                    name: "Move".to_owned(),
+                    discriminant: None,
+                    fields: Fields::Unnamed(vec![Shape::Array {
+                        len: 2,
+                        element: Box::new(primitive("f32")),
+                    }]),
+                },
+                Variant {
+                    name: "Quit".to_owned(),
+                    discriminant: None,
+                    fields: Fields::Unit,
+                },
+            ],
+        }
+    );
+}
+
+#[test]
+fn shape_of_collections() {
+    assert_eq!(
+        <Vec<u8>>::shape(),
+        Shape::Sequence {
+            name: "std::vec::Vec".to_owned(),
+            element: Box::new(primitive("u8")),
+            hasher: None,
+        }
+    );
+    assert_eq!(
+        <HashMap<String, &mut [bool]>>::shape(),
+        Shape::Map {
+            name: "std::collections::HashMap".to_owned(),
+            key: Box::new(opaque("std::string::String", vec![])),
+            value: Box::new(Shape::Reference {
+                mutable: true,
+                target: Box::new(Shape::Slice(Box::new(primitive("bool")))),
+            }),
+            hasher: None,
+        }
+    );
+}
+
+#[test]
+fn core_and_alloc_types_are_named_by_their_std_path() {
+    extern crate alloc;
+    assert_eq!(
+        <alloc::vec::Vec<u8>>::shape(),
+        Shape::Sequence {
+            name: "std::vec::Vec".to_owned(),
+            element: Box::new(primitive("u8")),
+            hasher: None,
+        }
+    );
+    assert_eq!(
+        <alloc::collections::BTreeMap<u8, u8>>::type_hash(),
+        <std::collections::BTreeMap<u8, u8>>::type_hash()
+    );
+    assert_eq!(
+        <core::cell::Cell<u8>>::shape(),
+        opaque("std::cell::Cell", vec![primitive("u8")])
+    );
+    assert_eq!(
+        <alloc::sync::Arc<str>>::shape(),
+        opaque("std::sync::Arc", vec![primitive("str")])
+    );
+    assert_eq!(
+        <core::net::Ipv4Addr>::shape(),
+        opaque("std::net::Ipv4Addr", vec![])
+    );
+}
+
+#[test]
+fn shape_of_recursive_type_has_back_reference() {
+    assert_eq!(
+        v1::Node::shape(),
+        Shape::Struct {
+            name: "Node".to_owned(),
+            fields: Fields::Unnamed(vec![Shape::Sequence {
+                name: "std::vec::Vec".to_owned(),
+                element: Box::new(Shape::BackRef {
+                    depth: 0,
+                    name: "Node".to_owned(),
+                }),
+                hasher: None,
+            }]),
+        }
+    );
+}
+
+#[test]
+fn shape_of_field_attributes() {
+    assert_eq!(
+        v1::Attributes::shape(),
+        Shape::Struct {
+            name: "Attributes".to_owned(),
+            fields: Fields::Named(vec![
+                Field {
+                    name: "renamed".to_owned(),
+                    shape: primitive("u32"),
+                },
+                Field {
+                    name: "foreign".to_owned(),
+                    shape: Shape::Foreign("Foreign".to_owned()),
+                },
+            ]),
+        }
+    );
+}
+
+#[test]
+fn hash_is_hash_of_shape() {
+    assert_eq!(v1::Point::shape().type_hash(), v1::Point::type_hash());
+    assert_eq!(v1::Message::shape().type_hash(), v1::Message::TYPE_HASH);
+    assert_eq!(v1::Node::shape().type_hash_128(), v1::Node::type_hash_128());
+    assert_eq!(
+        <(u8, *const str)>::shape().type_hash(),
+        <(u8, *const str)>::TYPE_HASH
+    );
+}
+
+mod v1 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct Point {
+        x: i32,
+        label: Option<char>,
+    }
+
+    #[derive(TypeHash)]
+    pub enum Message {
+        Move([f32; 2]),
+        Quit,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Node(Vec<Node>);
+
+    #[derive(TypeHash)]
+    pub struct Attributes {
+        #[type_hash(skip)]
+        skipped: bool,
+        #[type_hash(as = "u32")]
+        renamed: u64,
+        #[type_hash(foreign_type)]
+        foreign: Foreign,
+    }
+
+    pub struct Foreign;
+}
@@ -0,0 +1,223 @@
+#![cfg(feature = "std")]
+#![allow(unused)]
+use std::fs;
+use std::path::PathBuf;
+use type_hash::assert_type_hash;
+use type_hash::snapshot::Assertion;
+use type_hash::TypeHash;
+
+#[test]
+fn assert_expected_hash() {
+    assert_type_hash!(v1::Message, 0x8a0eeb94bb00c95e);
+    assert_type_hash!(v1::Message, 9948147650933082462);
+    assert_type_hash!(bool, 0xfc0122bf7c094258,);
+}
+
+// A crate in a temporary directory, with a source file containing `source`
+fn temp_crate(name: &str, source: &str) -> (&'static str, PathBuf) {
+    let dir = std::env::temp_dir().join(format!(
+        "type_hash_snapshot_{}_{}",
+        name,
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("tests")).unwrap();
+    let file = dir.join("tests/example.rs");
+    fs::write(&file, source).unwrap();
+    (Box::leak(dir.to_str().unwrap().into()), file)
+}
+
+fn assertion(manifest_dir: &'static str, expected: u64, literal: &'static str) -> Assertion {
+    Assertion {
+        type_name: "Message",
+        expected,
+        literal,
+        manifest_dir,
+        file: "tests/example.rs",
+        line: 3,
+        column: 5,
+    }
+}
+
+const SOURCE: &str = "#[test]
+fn message() {
+    assert_type_hash!(Message, 0x1234); // 0x1234
+    assert_type_hash!(Other, 0x1234);
+}
+";
+
+#[test]
+fn mismatch_reports_both_hashes() {
+    let (dir, _) = temp_crate("mismatch", SOURCE);
+    let mismatch = assertion(dir, 0x1234, "0x1234")
+        .check(&v1::Message::shape())
+        .unwrap_err();
+    assert_eq!(mismatch.expected, 0x1234);
+    assert_eq!(mismatch.found, v1::Message::type_hash());
+    assert_eq!(mismatch.changes, None);
+    let message = mismatch.to_string();
+    assert!(message.starts_with(
+        "the hash of `Message` has changed
+  expected: 0x0000000000001234
+     found: 0x8a0eeb94bb00c95e
+"
+    ));
+    assert!(message.ends_with("set TYPE_HASH_UPDATE=1 to update the expected hash"));
+}
+
+#[test]
+fn mismatch_without_recorded_shape_shows_the_structure() {
+    let (dir, _) = temp_crate("structure", SOURCE);
+    let mismatch = assertion(dir, 0x1234, "0x1234")
+        .check(&v2::Message::shape())
+        .unwrap_err();
+    assert_eq!(
+        mismatch.structure,
+        ["Message::LaunchMissiles.destination: u64"]
+    );
+    assert_eq!(
+        mismatch.to_string(),
+        format!(
+            "the hash of `Message` has changed
+  expected: 0x0000000000001234
+     found: {:#018x}
+the shape for the expected hash was not recorded
+structure:
+  Message::LaunchMissiles.destination: u64
+set TYPE_HASH_UPDATE=1 to update the expected hash",
+            v2::Message::type_hash()
+        )
+    );
+}
+
+#[test]
+fn update_replaces_only_the_asserted_literal() {
+    let (dir, file) = temp_crate("update", SOURCE);
+    assertion(dir, 0x1234, "0x1234")
+        .update(&v1::Message::shape())
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "#[test]
+fn message() {
+    assert_type_hash!(Message, 0x8a0eeb94bb00c95e); // 0x1234
+    assert_type_hash!(Other, 0x1234);
+}
+"
+    );
+    assert!(assertion(dir, 0x8a0eeb94bb00c95e, "0x8a0eeb94bb00c95e")
+        .check(&v1::Message::shape())
+        .is_ok());
+}
+
+#[test]
+fn update_skips_the_literal_in_the_type() {
+    let (dir, file) = temp_crate(
+        "type_with_digit",
+        "#[test]
+fn message() {
+    assert_type_hash!(Msg0<fn(u8) -> [u8; 0]>, 0);
+}
+",
+    );
+    assertion(dir, 0, "0")
+        .update(&v1::Message::shape())
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "#[test]
+fn message() {
+    assert_type_hash!(Msg0<fn(u8) -> [u8; 0]>, 0x8a0eeb94bb00c95e);
+}
+"
+    );
+}
+
+#[test]
+fn update_fails_if_the_literal_is_not_found() {
+    let (dir, _) = temp_crate("not_found", SOURCE);
+    assert!(assertion(dir, 0x99, "0x99")
+        .update(&v1::Message::shape())
+        .is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn mismatch_lists_changes_since_the_recorded_shape() {
+    let (dir, file) = temp_crate("changes", SOURCE);
+    let old = assertion(dir, 0x1234, "0x1234");
+    old.update(&v1::Message::shape()).unwrap();
+    assert_eq!(
+        old.shape_path(),
+        file.with_file_name("snapshots/example__Message.json")
+    );
+    let recorded = assertion(dir, 0x8a0eeb94bb00c95e, "0x8a0eeb94bb00c95e");
+    let mismatch = recorded.check(&v2::Message::shape()).unwrap_err();
+    assert_eq!(
+        mismatch.to_string(),
+        format!(
+            "the hash of `Message` has changed
+  expected: 0x8a0eeb94bb00c95e
+     found: {:#018x}
+changes:
+  Message::LaunchMissiles.destination: String -> u64
+  Message::CancelMissiles removed
+set TYPE_HASH_UPDATE=1 to update the expected hash",
+            v2::Message::type_hash()
+        )
+    );
+}
+
+#[cfg(not(feature = "serde"))]
+#[test]
+fn mismatch_lists_lines_changed_since_the_recorded_structure() {
+    let (dir, file) = temp_crate("changed_lines", SOURCE);
+    let old = assertion(dir, 0x1234, "0x1234");
+    old.update(&v1::Message::shape()).unwrap();
+    assert_eq!(
+        old.shape_path(),
+        file.with_file_name("snapshots/example__Message.txt")
+    );
+    assert_eq!(
+        fs::read_to_string(old.shape_path()).unwrap(),
+        "0x8a0eeb94bb00c95e
+Message::LaunchMissiles.destination: String
+Message::CancelMissiles
+"
+    );
+    let recorded = assertion(dir, 0x8a0eeb94bb00c95e, "0x8a0eeb94bb00c95e");
+    let mismatch = recorded.check(&v2::Message::shape()).unwrap_err();
+    assert_eq!(
+        mismatch.to_string(),
+        format!(
+            "the hash of `Message` has changed
+  expected: 0x8a0eeb94bb00c95e
+     found: {:#018x}
+changes:
+  - Message::LaunchMissiles.destination: String
+  - Message::CancelMissiles
+  + Message::LaunchMissiles.destination: u64
+set TYPE_HASH_UPDATE=1 to update the expected hash",
+            v2::Message::type_hash()
+        )
+    );
+}
+
+mod v1 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub enum Message {
+        LaunchMissiles { destination: String },
+        CancelMissiles,
+    }
+}
+
+mod v2 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub enum Message {
+        LaunchMissiles { destination: u64 },
+    }
+}
@@ -0,0 +1,333 @@
+#![allow(
+    unused,
+    clippy::missing_safety_doc,
+    clippy::needless_arbitrary_self_type
+)]
+use type_hash::diff::diff;
+use type_hash::shape::{Method, Shape};
+use type_hash::TypeHash;
+
+#[test]
+fn trait_object_shape() {
+    assert_eq!(
+        <dyn v1::Handler>::shape(),
+        Shape::Trait {
+            name: "Handler".into(),
+            supertraits: vec![],
+            methods: vec![
+                Method {
+                    name: "name".into(),
+                    receiver: Some("&self".into()),
+                    is_unsafe: false,
+                    args: vec![],
+                    output: <&str>::shape(),
+                },
+                Method {
+                    name: "handle".into(),
+                    receiver: Some("&mut self".into()),
+                    is_unsafe: false,
+                    args: vec![u8::shape(), <&[u8]>::shape()],
+                    output: bool::shape(),
+                },
+            ],
+            auto_traits: vec![],
+        }
+    );
+}
+
+#[test]
+fn trait_objects_are_displayed_as_types() {
+    assert_eq!(<dyn v1::Handler>::shape().to_string(), "dyn Handler");
+    assert_eq!(
+        <dyn v1::Handler + Send + Sync>::shape().to_string(),
+        "dyn Handler + Send + Sync"
+    );
+    assert_eq!(<dyn Fn(u8) -> bool>::shape().to_string(), "dyn Fn");
+}
+
+#[test]
+fn supertraits_and_receivers() {
+    match <dyn Plugin + Send>::shape() {
+        Shape::Trait {
+            supertraits,
+            methods,
+            auto_traits,
+            ..
+        } => {
+            assert_eq!(
+                supertraits,
+                [Shape::Foreign("Send".into()), Shape::Foreign("Sync".into())]
+            );
+            assert_eq!(auto_traits, ["Send"]);
+            let methods: Vec<_> = methods.iter().map(ToString::to_string).collect();
+            assert_eq!(
+                methods,
+                [
+                    "fn name(&self, &str) -> &str",
+                    "unsafe fn raw(self: Box<Self>, *const u8)"
+                ]
+            );
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn receivers_are_normalised() {
+    let receivers: Vec<_> = match <dyn Receivers>::shape() {
+        Shape::Trait { methods, .. } => methods.into_iter().map(|m| m.receiver).collect(),
+        _ => unreachable!(),
+    };
+    assert_eq!(
+        receivers,
+        [
+            Some("self"),
+            Some("self: Box<Self>"),
+            Some("&self"),
+            Some("&mut self"),
+            Some("self: Box<Self>"),
+            Some("self: Rc<Self>"),
+            Some("self: Arc<Self>"),
+            Some("self: Pin<&mut Self>"),
+        ]
+        .map(|receiver| receiver.map(String::from))
+    );
+    assert_eq!(<dyn Receivers>::TYPE_HASH, <dyn Receivers>::type_hash());
+}
+
+#[test]
+fn supertraits_are_hashed_by_their_methods() {
+    match <dyn v1::Logger>::shape() {
+        Shape::Trait { supertraits, .. } => assert_eq!(
+            supertraits,
+            [<dyn v1::Handler>::shape(), Shape::Foreign("Debug".into())]
+        ),
+        _ => unreachable!(),
+    }
+    assert_ne!(<dyn v1::Logger>::type_hash(), <dyn v2::Logger>::type_hash());
+    assert_eq!(<dyn v1::Logger>::TYPE_HASH, <dyn v1::Logger>::type_hash());
+    let changes: Vec<_> = diff(&<dyn v1::Logger>::shape(), &<dyn v2::Logger>::shape())
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(changes, ["Logger: Handler::handle(1): &[u8] -> Vec<u8>"]);
+}
+
+#[test]
+fn auto_traits_are_part_of_the_hash() {
+    let hashes = [
+        <dyn v1::Handler>::type_hash(),
+        <dyn v1::Handler + Send>::type_hash(),
+        <dyn v1::Handler + Sync>::type_hash(),
+        <dyn v1::Handler + Send + Sync>::type_hash(),
+    ];
+    for (i, a) in hashes.iter().enumerate() {
+        for b in &hashes[i + 1..] {
+            assert_ne!(a, b);
+        }
+    }
+    assert_eq!(
+        <dyn v1::Handler + Sync + Send>::type_hash(),
+        <dyn v1::Handler + Send + Sync>::type_hash()
+    );
+}
+
+#[test]
+fn method_signatures_are_part_of_the_hash() {
+    assert_ne!(
+        <dyn v1::Handler>::type_hash(),
+        <dyn v2::Handler>::type_hash()
+    );
+    assert_ne!(
+        <dyn v1::Handler>::type_hash(),
+        <dyn v3::Handler>::type_hash()
+    );
+    assert_ne!(
+        <dyn v1::Handler>::type_hash(),
+        <dyn v4::Handler>::type_hash()
+    );
+}
+
+#[test]
+fn methods_that_require_sized_are_not_part_of_the_hash() {
+    assert_eq!(
+        <dyn v1::Handler>::type_hash(),
+        <dyn v5::Handler>::type_hash()
+    );
+}
+
+#[test]
+fn trait_objects_in_types() {
+    assert_eq!(
+        <Box<dyn v1::Handler>>::shape(),
+        Shape::Opaque {
+            name: "std::boxed::Box".into(),
+            args: vec![<dyn v1::Handler>::shape()],
+        }
+    );
+    assert_eq!(
+        <&dyn v1::Handler>::type_hash(),
+        <&'static dyn v1::Handler>::type_hash()
+    );
+}
+
+#[test]
+fn recursive_traits() {
+    let shape = <dyn Node>::shape();
+    match &shape {
+        Shape::Trait { methods, .. } => {
+            assert_eq!(methods[0].output.to_string(), "Vec<Box<dyn Node>>")
+        }
+        _ => unreachable!(),
+    }
+    assert_eq!(shape.type_hash(), <dyn Node>::type_hash());
+}
+
+#[test]
+fn closure_traits() {
+    let hashes = [
+        <dyn Fn(u8) -> bool>::type_hash(),
+        <dyn FnMut(u8) -> bool>::type_hash(),
+        <dyn FnOnce(u8) -> bool>::type_hash(),
+        <dyn Fn(u8) -> bool + Send>::type_hash(),
+        <dyn Fn(u16) -> bool>::type_hash(),
+        <dyn Fn(u8)>::type_hash(),
+    ];
+    for (i, a) in hashes.iter().enumerate() {
+        for b in &hashes[i + 1..] {
+            assert_ne!(a, b);
+        }
+    }
+    assert_eq!(
+        <dyn Fn(u8) -> bool>::shape(),
+        Shape::Trait {
+            name: "std::ops::Fn".into(),
+            supertraits: vec![],
+            methods: vec![Method {
+                name: "call".into(),
+                receiver: Some("&self".into()),
+                is_unsafe: false,
+                args: vec![u8::shape()],
+                output: bool::shape(),
+            }],
+            auto_traits: vec![],
+        }
+    );
+}
+
+#[test]
+fn diff_of_traits() {
+    let changes: Vec<_> = diff(&<dyn v1::Handler>::shape(), &<dyn v2::Handler>::shape())
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(changes, ["Handler::handle(1): &[u8] -> Vec<u8>"]);
+    let changes: Vec<_> = diff(&<dyn v1::Handler>::shape(), &<dyn v3::Handler>::shape())
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(
+        changes,
+        ["Handler::handle: fn handle(&mut self, u8, &[u8]) -> bool -> fn handle(&self, u8, &[u8]) -> bool"]
+    );
+    let changes: Vec<_> = diff(&<dyn v1::Handler>::shape(), &<dyn v4::Handler>::shape())
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(changes, ["Handler::name removed", "Handler::close added"]);
+}
+
+#[test]
+fn const_hash_of_traits() {
+    assert_eq!(<dyn v1::Handler>::TYPE_HASH, <dyn v1::Handler>::type_hash());
+    assert_eq!(
+        <dyn v1::Handler + Send>::TYPE_HASH,
+        <dyn v1::Handler + Send>::type_hash()
+    );
+    assert_eq!(
+        <dyn FnMut(u8, String)>::TYPE_HASH,
+        <dyn FnMut(u8, String)>::type_hash()
+    );
+    assert_eq!(<dyn Plugin>::TYPE_HASH, <dyn Plugin>::type_hash());
+}
+
+#[type_hash::trait_hash]
+pub trait Node {
+    fn children(&self) -> Vec<Box<dyn Node>>;
+}
+
+#[type_hash::trait_hash]
+pub trait Plugin: Send + Sync {
+    fn name<'a>(&'a self, prefix: &'a str) -> &'a str;
+    unsafe fn raw(self: Box<Self>, ptr: *const u8);
+}
+
+#[type_hash::trait_hash]
+pub trait Receivers {
+    fn by_value(self);
+    fn by_mut_value(mut self)
+    where
+        Self: Sized,
+    {
+    }
+    fn by_mut_value_on_box(mut self: Box<Self>) {}
+    fn by_ref(self: &Self);
+    fn by_mut(&'static mut self);
+    fn boxed(self: Box<Self>);
+    fn rc(self: std::rc::Rc<Self>);
+    fn arc(self: std::sync::Arc<Self>);
+    fn pinned(self: std::pin::Pin<&mut Self>);
+}
+
+mod v1 {
+    #[type_hash::trait_hash]
+    pub trait Handler {
+        fn name(&self) -> &str;
+        fn handle(&mut self, id: u8, data: &[u8]) -> bool;
+    }
+
+    #[type_hash::trait_hash]
+    pub trait Logger: Handler + std::fmt::Debug {
+        fn log(&self, message: &str);
+    }
+}
+
+mod v2 {
+    #[type_hash::trait_hash]
+    pub trait Handler {
+        fn name(&self) -> &str;
+        fn handle(&mut self, id: u8, data: Vec<u8>) -> bool;
+    }
+
+    #[type_hash::trait_hash]
+    pub trait Logger: Handler + std::fmt::Debug {
+        fn log(&self, message: &str);
+    }
+}
+
+mod v3 {
+    #[type_hash::trait_hash]
+    pub trait Handler {
+        fn name(&self) -> &str;
+        fn handle(&self, id: u8, data: &[u8]) -> bool;
+    }
+}
+
+mod v4 {
+    #[type_hash::trait_hash]
+    pub trait Handler {
+        fn handle(&mut self, id: u8, data: &[u8]) -> bool;
+        fn close(&mut self);
+    }
+}
+
+mod v5 {
+    #[type_hash::trait_hash]
+    pub trait Handler {
+        fn name(&self) -> &str;
+        fn handle(&mut self, id: u8, data: &[u8]) -> bool;
+        fn new() -> Self
+        where
+            Self: Sized;
+    }
+}
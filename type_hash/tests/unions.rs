@@ -0,0 +1,131 @@
+#![allow(unused)]
+use type_hash::shape::{Field, Fields, Shape};
+use type_hash::TypeHash;
+
+#[test]
+fn union_shape() {
+    assert_eq!(
+        v1::Value::shape(),
+        Shape::Union {
+            name: "Value".into(),
+            fields: vec![
+                Field {
+                    name: "int".into(),
+                    shape: i64::shape(),
+                },
+                Field {
+                    name: "float".into(),
+                    shape: f64::shape(),
+                },
+            ],
+        }
+    );
+}
+
+#[test]
+fn const_type_hash_is_same_as_type_hash() {
+    assert_eq!(v1::Value::TYPE_HASH, v1::Value::type_hash());
+    assert_eq!(v1::Generic::<u8>::TYPE_HASH, v1::Generic::<u8>::type_hash());
+}
+
+#[test]
+fn union_has_different_hash_to_struct_with_same_fields() {
+    assert_ne!(v1::Value::type_hash(), v2::Value::type_hash());
+}
+
+#[test]
+fn union_with_changed_field_has_different_hash() {
+    assert_ne!(v1::Value::type_hash(), v3::Value::type_hash());
+    assert_ne!(v1::Renamed::type_hash(), v3::Renamed::type_hash());
+    assert_ne!(v1::Reordered::type_hash(), v3::Reordered::type_hash());
+}
+
+#[test]
+fn skipped_and_generic_fields() {
+    assert_eq!(v1::Skipped::type_hash(), v3::Skipped::type_hash());
+    assert_ne!(
+        v1::Generic::<u8>::type_hash(),
+        v1::Generic::<u16>::type_hash()
+    );
+}
+
+#[test]
+fn diff_of_unions() {
+    let changes: Vec<_> = type_hash::diff::diff(&v1::Value::shape(), &v3::Value::shape())
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(changes, ["Value.int: i64 -> u64"]);
+}
+
+mod v1 {
+    use std::mem::ManuallyDrop;
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    #[repr(C)]
+    pub union Value {
+        int: i64,
+        float: f64,
+    }
+
+    #[derive(TypeHash)]
+    pub union Renamed {
+        a: u32,
+    }
+
+    #[derive(TypeHash)]
+    pub union Reordered {
+        a: u32,
+        b: f32,
+    }
+
+    #[derive(TypeHash)]
+    pub union Skipped {
+        a: u32,
+        #[type_hash(skip)]
+        b: ManuallyDrop<String>,
+    }
+
+    #[derive(TypeHash)]
+    pub union Generic<T: Copy> {
+        value: T,
+        bytes: [u8; 4],
+    }
+}
+
+mod v2 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct Value {
+        int: i64,
+        float: f64,
+    }
+}
+
+mod v3 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub union Value {
+        int: u64,
+        float: f64,
+    }
+
+    #[derive(TypeHash)]
+    pub union Renamed {
+        b: u32,
+    }
+
+    #[derive(TypeHash)]
+    pub union Reordered {
+        b: f32,
+        a: u32,
+    }
+
+    #[derive(TypeHash)]
+    pub union Skipped {
+        a: u32,
+    }
+}
@@ -0,0 +1,118 @@
+#![allow(unused)]
+use type_hash::diff::diff;
+use type_hash::TypeHash;
+
+#[test]
+fn skipped_variants_are_not_hashed() {
+    assert_eq!(v1::Message::type_hash(), v2::Message::type_hash());
+    assert_eq!(v1::Message::TYPE_HASH, v2::Message::TYPE_HASH);
+    assert_eq!(v1::Message::shape(), v2::Message::shape());
+}
+
+#[test]
+fn renamed_variants_keep_their_hash() {
+    assert_eq!(v1::Command::type_hash(), v2::Command::type_hash());
+    assert_eq!(v1::Command::TYPE_HASH, v2::Command::TYPE_HASH);
+    assert!(diff(&v1::Command::shape(), &v2::Command::shape()).is_empty());
+}
+
+#[test]
+fn variants_hash_as_another_payload() {
+    assert_eq!(v1::Event::type_hash(), v2::Event::type_hash());
+    assert_eq!(v1::Event::TYPE_HASH, v2::Event::TYPE_HASH);
+    assert_eq!(v1::Event::shape(), v2::Event::shape());
+}
+
+#[test]
+fn variant_attributes_change_the_hash() {
+    assert_ne!(v1::Message::type_hash(), v3::Message::type_hash());
+    assert_ne!(v1::Command::type_hash(), v3::Command::type_hash());
+    assert_ne!(v1::Event::type_hash(), v3::Event::type_hash());
+    let changes: Vec<_> = diff(&v1::Command::shape(), &v3::Command::shape())
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(changes, ["Command::Launch removed", "Command::Fire added"]);
+}
+
+mod v1 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub enum Message {
+        LaunchMissiles { destination: String },
+        CancelMissiles,
+    }
+
+    #[derive(TypeHash)]
+    pub enum Command {
+        Launch(u8),
+        Cancel,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Position {
+        x: i32,
+        y: i32,
+    }
+
+    #[derive(TypeHash)]
+    pub enum Event {
+        Moved(Position),
+        Stopped,
+    }
+}
+
+mod v2 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub enum Message {
+        LaunchMissiles {
+            destination: String,
+        },
+        #[type_hash(skip)]
+        Ping(std::time::Instant),
+        CancelMissiles,
+    }
+
+    #[derive(TypeHash)]
+    pub enum Command {
+        #[type_hash(rename = "Launch")]
+        Fire(u8),
+        Cancel,
+    }
+
+    #[derive(TypeHash)]
+    pub enum Event {
+        #[type_hash(as = "super::v1::Position")]
+        Moved {
+            x: i32,
+            y: i32,
+        },
+        Stopped,
+    }
+}
+
+mod v3 {
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub enum Message {
+        LaunchMissiles { destination: String },
+        Ping,
+        CancelMissiles,
+    }
+
+    #[derive(TypeHash)]
+    pub enum Command {
+        Fire(u8),
+        Cancel,
+    }
+
+    #[derive(TypeHash)]
+    pub enum Event {
+        Moved { x: i32, y: i32 },
+        Stopped,
+    }
+}
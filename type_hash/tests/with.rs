@@ -0,0 +1,175 @@
+#![allow(unused)]
+use std::hash::{Hash, Hasher};
+use type_hash::diff::{diff, ChangeKind};
+use type_hash::shape::{Fields, Shape};
+use type_hash::TypeHash;
+
+mod external {
+    pub struct Uuid(pub [u8; 16]);
+    pub struct Guid(pub u128);
+}
+
+fn uuid(hasher: &mut impl Hasher) {
+    hasher.write(b"uuid");
+}
+
+fn uuid_v2(hasher: &mut impl Hasher) {
+    hasher.write(b"uuid-v2");
+}
+
+#[test]
+fn with_function_describes_the_field() {
+    assert_eq!(v1::User::type_hash(), v2::User::type_hash());
+    assert_eq!(v1::Id::type_hash(), v2::Id::type_hash());
+    assert_eq!(v1::Event::type_hash(), v2::Event::type_hash());
+    assert!(diff(&v1::User::shape(), &v2::User::shape()).is_empty());
+    assert_eq!(Vec::<v1::User>::type_hash(), Vec::<v2::User>::type_hash());
+}
+
+#[test]
+fn with_function_shape() {
+    let fields = match v1::Id::shape() {
+        Shape::Struct { fields, .. } => fields,
+        shape => panic!("unexpected shape {:?}", shape),
+    };
+    assert_eq!(
+        fields,
+        Fields::Unnamed(vec![Shape::Custom {
+            name: "external :: Uuid".into(),
+            bytes: b"uuid".to_vec(),
+        }])
+    );
+    assert_eq!(
+        v1::Id::shape(),
+        Shape::Struct {
+            name: "Id".into(),
+            fields
+        }
+    );
+}
+
+fn guid(hasher: &mut impl Hasher) {
+    hasher.write_u32(0x0102_0304);
+    hasher.write_usize(5);
+    hasher.write_i16(-2);
+    16usize.hash(hasher);
+}
+
+#[test]
+fn integers_are_written_little_endian_with_fixed_width() {
+    assert_eq!(
+        v1::Guid::shape(),
+        Shape::Struct {
+            name: "Guid".into(),
+            fields: Fields::Unnamed(vec![Shape::Custom {
+                name: "external :: Guid".into(),
+                bytes: vec![
+                    4, 3, 2, 1, // write_u32
+                    5, 0, 0, 0, 0, 0, 0, 0, // write_usize
+                    0xfe, 0xff, // write_i16
+                    16, 0, 0, 0, 0, 0, 0, 0, // usize::hash
+                ],
+            }]),
+        }
+    );
+}
+
+#[test]
+fn changing_the_description_changes_the_hash() {
+    assert_ne!(v1::User::type_hash(), v3::User::type_hash());
+    assert_ne!(v1::Id::type_hash(), v3::Id::type_hash());
+    assert_ne!(v1::Event::type_hash(), v3::Event::type_hash());
+    let changes: Vec<_> = diff(&v1::User::shape(), &v3::User::shape())
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(changes, ["User.id: custom encoding of `Uuid` changed"]);
+    assert_eq!(
+        diff(&v1::User::shape(), &v3::User::shape())[0].kind,
+        ChangeKind::Custom {
+            name: "Uuid".into(),
+            old: b"uuid".to_vec(),
+            new: b"uuid-v2".to_vec(),
+        }
+    );
+}
+
+mod v1 {
+    use super::external::Uuid;
+    use super::{external, uuid};
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct User {
+        #[type_hash(with = "uuid")]
+        id: Uuid,
+        name: String,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Id(#[type_hash(with = "uuid")] external::Uuid);
+
+    #[derive(TypeHash)]
+    pub struct Guid(#[type_hash(with = "super::guid")] external::Guid);
+
+    #[derive(TypeHash)]
+    pub enum Event {
+        Created(#[type_hash(with = "uuid")] Uuid),
+        Renamed {
+            #[type_hash(with = "super::uuid")]
+            id: Uuid,
+            name: String,
+        },
+    }
+}
+
+mod v2 {
+    use super::external::Guid;
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct User {
+        #[type_hash(with = "super::uuid")]
+        id: Guid,
+        name: String,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Id(#[type_hash(with = "super::uuid")] Guid);
+
+    #[derive(TypeHash)]
+    pub enum Event {
+        Created(#[type_hash(with = "super::uuid")] Guid),
+        Renamed {
+            #[type_hash(with = "super::uuid")]
+            id: Guid,
+            name: String,
+        },
+    }
+}
+
+mod v3 {
+    use super::external::Uuid;
+    use super::{external, uuid_v2};
+    use type_hash::TypeHash;
+
+    #[derive(TypeHash)]
+    pub struct User {
+        #[type_hash(with = "uuid_v2")]
+        id: Uuid,
+        name: String,
+    }
+
+    #[derive(TypeHash)]
+    pub struct Id(#[type_hash(with = "uuid_v2")] external::Uuid);
+
+    #[derive(TypeHash)]
+    pub enum Event {
+        Created(#[type_hash(with = "uuid_v2")] Uuid),
+        Renamed {
+            #[type_hash(with = "uuid_v2")]
+            id: Uuid,
+            name: String,
+        },
+    }
+}